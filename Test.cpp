@@ -1,18 +1,20 @@
 #include "Test.h"
+#include <Prelude.h>
 namespace Test {
+using namespace Prelude;
 template <typename T = int, typename U = int>
 T Add(T t, U u) {
 return t + u;
 }
-template <typename T = Float, typename U = Float>
+template <typename T = double, typename U = double>
 T Add(T t, U u) {
 return t + u;
 }
-template <typename T = int, typename U = Float>
+template <typename T = int, typename U = double>
 U Add(T t, U u) {
 return t + u;
 }
-template <typename T = Float, typename U = int>
+template <typename T = double, typename U = int>
 T Add(T t, U u) {
 return t + u;
 }
@@ -1,14 +1,15 @@
 #ifndef TEST_REAL_H
 #define TEST_REAL_H
+#include <Prelude.h>
 namespace Test {
-using Float = int;
+using namespace Prelude;
 template <typename T = int, typename U = int>
 T Add(T t, U u);
-template <typename T = Float, typename U = Float>
+template <typename T = double, typename U = double>
 T Add(T t, U u);
-template <typename T = int, typename U = Float>
+template <typename T = int, typename U = double>
 U Add(T t, U u);
-template <typename T = Float, typename U = int>
+template <typename T = double, typename U = int>
 T Add(T t, U u);
 }
 #endif // TEST_REAL_H
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use parser::parser::{
+    Statement,
+    Type,
+    EnumVarient,
+};
+
+use crate::Codegen;
+
+// A data type that holds itself, directly or through other data types, would have infinite size
+// in C++. The fields that close such a loop are kept in a `Box` on the heap instead, which Real
+// code doesn't see: construction converts to a `Box` and reading a field dereferences it.
+impl Codegen {
+    // The C++ type of field `field` of `owner`, a data struct or an `Enum.Variant`.
+    pub(crate) fn get_field_type(&self, owner: &str, field: &str, t: Type) -> String {
+        if self.is_boxed(owner, field) {
+            format!("Box<{}>", self.get_type(t))
+        } else {
+            self.get_type(t)
+        }
+    }
+    // `value.field`, dereferenced when the field is boxed.
+    pub(crate) fn get_field(&self, owner: &str, value: &str, field: &str) -> String {
+        if self.is_boxed(owner, field) {
+            format!("(*{}.{})", value, field)
+        } else {
+            format!("{}.{}", value, field)
+        }
+    }
+    // Without the struct `value` belongs to there is only the field's name, so it's dereferenced
+    // when every data struct with a field of that name boxes it.
+    pub(crate) fn get_member(&self, owner: Option<&str>, value: &str, field: &str) -> String {
+        if let Some(owner) = owner.filter(|owner| self.structs.contains_key(*owner)) {
+            return self.get_field(owner, value, field);
+        }
+        let mut owners = self.structs.iter().filter(|(_, fields)| fields.iter().any(|(name, _)| name == field)).peekable();
+        if owners.peek().is_some() && owners.all(|(owner, _)| self.is_boxed(owner, field)) {
+            format!("(*{}.{})", value, field)
+        } else {
+            format!("{}.{}", value, field)
+        }
+    }
+    fn is_boxed(&self, owner: &str, field: &str) -> bool {
+        self.boxed.get(owner).is_some_and(|fields| fields.iter().any(|boxed| boxed == field))
+    }
+
+    // Every data type that holds itself is declared before any of them is defined, so the
+    // `Box`es in their fields can name each other whatever order they were written in.
+    pub(crate) fn get_forward_declarations(&self) -> String {
+        let mut code: String = String::new();
+        for statement in self.statements.iter() {
+            match statement.undocumented() {
+                Statement::DataEnum(name, _, variants, _) if self.recursive.contains(name) => {
+                    let names: Vec<String> = variants.iter().map(|variant| match variant {
+                        EnumVarient::Tuple(name, _, _) | EnumVarient::Unit(name, _) => name.clone(),
+                    }).collect();
+                    code.push_str(&format!("namespace {}_Variants {{\n", name));
+                    for variant in names.iter() {
+                        code.push_str(&format!("struct {};\n", variant));
+                    }
+                    code.push_str("}\n");
+                    let names: Vec<String> = names.iter().map(|variant| format!("{}_Variants::{}", name, variant)).collect();
+                    code.push_str(&format!("using {} = Enum<{}>;\n", name, names.join(", ")));
+                }
+                Statement::GenericDataEnum(name, _, generics, variants, _) if self.recursive.contains(name) => {
+                    let names: Vec<String> = variants.iter().map(|variant| match variant {
+                        EnumVarient::Tuple(name, _, _) | EnumVarient::Unit(name, _) => name.clone(),
+                    }).collect();
+                    code.push_str(&format!("namespace {}_Variants {{\n", name));
+                    for variant in names.iter() {
+                        code.push_str(&self.get_parameters_template(&self.get_variant_parameters(name, variant)));
+                        code.push_str(&format!("struct {};\n", variant));
+                    }
+                    code.push_str("}\n");
+                    let names: Vec<String> = names.iter().map(|variant| self.get_variant_path(name, variant, &HashMap::new())).collect();
+                    code.push_str(&self.get_template(generics));
+                    code.push_str(&format!("using {} = Enum<{}>;\n", name, names.join(", ")));
+                }
+                Statement::DataStruct(name, _, _, _) if self.recursive.contains(name) => {
+                    code.push_str(&format!("struct {};\n", name));
+                }
+                // Default type arguments are left to the definition, which may not repeat them.
+                Statement::GenericDataStruct(name, _, _, _, _) if self.recursive.contains(name) => {
+                    code.push_str(&self.get_parameters_template(self.generics.get(name).map(|names| names.as_slice()).unwrap_or_default()));
+                    code.push_str(&format!("struct {};\n", name));
+                }
+                _ => {}
+            }
+        }
+        code
+    }
+}
+
+// The fields to box, by struct name or `Enum.Variant`, and the data types that hold themselves.
+pub(crate) fn find_recursion(
+    variants: &HashMap<String, Vec<EnumVarient>>,
+    structs: &HashMap<String, Vec<(String, Type)>>,
+) -> (HashMap<String, Vec<String>>, Vec<String>) {
+    // Every field, with the data type it belongs to and the name of its owner in C++.
+    let mut fields: Vec<(String, String, String, Type)> = vec![];
+    for (name, enum_variants) in variants.iter() {
+        for variant in enum_variants.iter() {
+            if let EnumVarient::Tuple(variant, types, _) = variant {
+                for (i, t) in types.iter().enumerate() {
+                    fields.push((name.clone(), format!("{}.{}", name, variant), format!("__{}", i), t.clone()));
+                }
+            }
+        }
+    }
+    for (name, struct_fields) in structs.iter() {
+        for (field, t) in struct_fields.iter() {
+            fields.push((name.clone(), name.clone(), field.clone(), t.clone()));
+        }
+    }
+    let mut held: HashMap<String, Vec<String>> = HashMap::new();
+    for (name, _, _, t) in fields.iter() {
+        let names: &mut Vec<String> = held.entry(name.clone()).or_default();
+        held_types(t, names);
+    }
+    let mut boxed: HashMap<String, Vec<String>> = HashMap::new();
+    for (name, owner, field, t) in fields.iter() {
+        let mut names: Vec<String> = vec![];
+        held_types(t, &mut names);
+        if names.iter().any(|held_name| reaches(held_name, name, &held, &mut vec![])) {
+            boxed.entry(owner.clone()).or_default().push(field.clone());
+        }
+    }
+    let recursive: Vec<String> = held.keys().filter(|name| {
+        held.get(*name).into_iter().flatten().any(|held_name| reaches(held_name, name, &held, &mut vec![]))
+    }).cloned().collect();
+    (boxed, recursive)
+}
+
+// The names of the types a value of type `t` holds by value, so not the elements of a list.
+// Type arguments count, as the generic type may hold them by value.
+fn held_types(t: &Type, names: &mut Vec<String>) {
+    match t {
+        Type::DataEnum(name, _) | Type::DataStruct(name, _) | Type::Unknown(name, _) => names.push(name.clone()),
+        Type::Optional(inner, _) => held_types(inner, names),
+        Type::Tuple(types, _) => {
+            for t in types.iter() {
+                held_types(t, names);
+            }
+        }
+        Type::GenericType(base, arguments, _) => {
+            held_types(base, names);
+            for argument in arguments.iter() {
+                held_types(argument, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Whether a value of data type `from` holds a `to` somewhere inside it, or is one.
+fn reaches(from: &str, to: &str, held: &HashMap<String, Vec<String>>, seen: &mut Vec<String>) -> bool {
+    if from == to {
+        return true;
+    }
+    if seen.iter().any(|name| name == from) {
+        return false;
+    }
+    seen.push(from.to_string());
+    held.get(from).into_iter().flatten().any(|name| reaches(name, to, held, seen))
+}
+
+#[cfg(test)]
+mod tests {
+    use lexer::{layout::Layout, lexer::Lexer, span::FileId};
+    use parser::parser::Parser;
+    use super::*;
+
+    fn compile(source: &str) -> Codegen {
+        let mut parser: Parser = Parser::new(Layout::new(Lexer::new(source, FileId(0))).collect());
+        let (statements, errors) = parser.parse();
+        assert!(errors.is_empty(), "{:?}", errors);
+        Codegen::new("M.real".to_string(), statements)
+    }
+
+    #[test]
+    fn only_the_fields_closing_a_loop_are_boxed() {
+        let codegen: Codegen = compile("module M
+data Expr =
+    | Num(Int)
+    | Neg(Stmt)
+data Stmt(value: Expr, line: Int)
+data Point(x: Int, y: Int)
+");
+        let mut boxed: Vec<(String, Vec<String>)> = codegen.boxed.clone().into_iter().collect();
+        boxed.sort();
+        assert_eq!(boxed, vec![
+            ("Expr.Neg".to_string(), vec!["__0".to_string()]),
+            ("Stmt".to_string(), vec!["value".to_string()]),
+        ]);
+        let mut recursive: Vec<String> = codegen.recursive.clone();
+        recursive.sort();
+        assert_eq!(recursive, vec!["Expr".to_string(), "Stmt".to_string()]);
+    }
+    #[test]
+    fn mutually_recursive_types_are_declared_before_either_is_defined() {
+        let codegen: Codegen = compile("module M
+data Expr =
+    | Num(Int)
+    | Neg(Stmt)
+data Stmt(value: Expr, line: Int)
+");
+        assert_eq!(codegen.get_forward_declarations(), "namespace Expr_Variants {
+struct Num;
+struct Neg;
+}
+using Expr = Enum<Expr_Variants::Num, Expr_Variants::Neg>;
+struct Stmt;
+");
+    }
+    #[test]
+    fn generic_recursive_types_are_declared_as_templates() {
+        let codegen: Codegen = compile("module M
+data Seq[T] =
+    | Nil
+    | Cons(T, Seq[T])
+data Tree[T, E >: Error](value: T, children: Seq[Tree[T, E]])
+");
+        assert_eq!(codegen.get_forward_declarations(), "namespace Seq_Variants {
+struct Nil;
+template <typename T>
+struct Cons;
+}
+template <typename T>
+using Seq = Enum<Seq_Variants::Nil, Seq_Variants::Cons<T>>;
+template <typename T, typename E>
+struct Tree;
+");
+        let Some(EnumVarient::Tuple(_, types, _)) = codegen.variants["Seq"].get(1).cloned() else {
+            panic!("expected `Cons` to have fields");
+        };
+        assert_eq!(codegen.get_field_type("Seq.Cons", "__1", types[1].clone()), "Box<Seq<T>>");
+    }
+}
@@ -1,8 +1,13 @@
 #![deny(dead_code)]
 #![allow(unused)]
 
+mod boxing;
+mod match_compiler;
+
 use std::collections::HashMap;
 
+use lexer::literal::integer_max;
+use lexer::span::Span;
 use lexer::tokens::TokenKind;
 use parser::parser::{
     Statement,
@@ -11,7 +16,9 @@ use parser::parser::{
     AccessFlag,
     EnumVarient,
     GenericType,
-    MatchCase
+    MatchCase,
+    Pattern,
+    StringPart,
 };
 
 #[derive(Debug, Clone)] pub struct Codegen {
@@ -21,12 +28,56 @@ use parser::parser::{
 
     pub enums: Vec<String>,
     pub types: HashMap<String, Type>,
+    // From the type checker, by the span of each `match`, the type of the value it matches and
+    // the type it gives, where they are known.
+    pub matched_types: HashMap<Span, Type>,
+    pub result_types: HashMap<Span, Type>,
+    // The variants of every data enum and the fields of every data struct, for patterns.
+    pub variants: HashMap<String, Vec<EnumVarient>>,
+    pub structs: HashMap<String, Vec<(String, Type)>>,
+    // The names of the type parameters of every generic data enum and data struct.
+    pub generics: HashMap<String, Vec<String>>,
+    // The fields kept in a `Box` by struct name or `Enum.Variant`, and the data types that hold themselves.
+    pub boxed: HashMap<String, Vec<String>>,
+    pub recursive: Vec<String>,
 
     pub current_class: Option<String>,
+    pub current_trait: Option<String>,
+    // Variables in scope in the procedure being generated, the ones a lambda can capture.
+    pub locals: Vec<String>,
+    // Inside `unsafe`, where indexing and slicing skip their bounds checks.
+    pub in_unsafe: bool,
+    // Numbers the values `let (a, b) = ...` destructures, so their names don't clash.
+    pub temporaries: usize,
 }
 
 impl Codegen {
     pub fn new(filename: String, statements: Vec<Statement>) -> Codegen {
+        let mut variants: HashMap<String, Vec<EnumVarient>> = HashMap::new();
+        let mut structs: HashMap<String, Vec<(String, Type)>> = HashMap::new();
+        let mut generics: HashMap<String, Vec<String>> = HashMap::new();
+        for statement in statements.iter() {
+            match statement.undocumented() {
+                Statement::GenericDataEnum(name, _, parameters, _, _) | Statement::GenericDataStruct(name, _, parameters, _, _) => {
+                    let names: Vec<String> = parameters.iter().filter_map(|(t, _, _)| match t {
+                        Type::GenericParameter(name, _) | Type::Generic(name, _) => Some(name.clone()),
+                        _ => None,
+                    }).collect();
+                    generics.insert(name.clone(), names);
+                }
+                _ => {}
+            }
+            match statement.undocumented() {
+                Statement::DataEnum(name, _, enum_variants, _) | Statement::GenericDataEnum(name, _, _, enum_variants, _) => {
+                    variants.insert(name.clone(), enum_variants.clone());
+                }
+                Statement::DataStruct(name, _, fields, _) | Statement::GenericDataStruct(name, _, _, fields, _) => {
+                    structs.insert(name.clone(), fields.clone());
+                }
+                _ => {}
+            }
+        }
+        let (boxed, recursive): (HashMap<String, Vec<String>>, Vec<String>) = boxing::find_recursion(&variants, &structs);
         Codegen {
             filename,
             statements,
@@ -34,8 +85,19 @@ impl Codegen {
 
             enums: Vec::new(),
             types: HashMap::new(),
+            matched_types: HashMap::new(),
+            result_types: HashMap::new(),
+            variants,
+            structs,
+            generics,
+            boxed,
+            recursive,
 
             current_class: None,
+            current_trait: None,
+            locals: Vec::new(),
+            in_unsafe: false,
+            temporaries: 0,
         }
     }
 
@@ -43,8 +105,8 @@ impl Codegen {
         let mut code = String::new();
         for statement in self.statements.iter() {
             if let Statement::Expression(Expression::Cpp(cpp, _), _) = statement {
-                if cpp.starts_with("#include") {
-                    code.push_str(cpp.as_str());
+                for line in cpp.lines().filter(|line| is_include(line)) {
+                    code.push_str(line);
                     code.push_str("\n");
                 }
             }
@@ -296,7 +358,16 @@ impl Codegen {
         code.push_str(&namespaces);
         code.push_str(&usings);
 
-        for statement in self.statements.clone() {
+        code.push_str(&self.get_forward_declarations());
+        // Types come first so procedures declared before them can still use them.
+        let (types, rest): (Vec<Statement>, Vec<Statement>) = self.statements.clone().into_iter().partition(|statement| {
+            matches!(statement.undocumented(),
+                Statement::Alias(..) | Statement::GenericAlias(..) |
+                Statement::DataEnum(..) | Statement::GenericDataEnum(..) |
+                Statement::DataStruct(..) | Statement::GenericDataStruct(..)
+            )
+        });
+        for statement in types.into_iter().chain(rest) {
             code.push_str(&self.get_header_statement(statement));
         }
 
@@ -309,6 +380,32 @@ impl Codegen {
 
     fn get_statement(&mut self, statement: Statement) -> String {
         match statement {
+            Statement::Let(name, Some(t), value, _) => {
+                self.types.insert(name.clone(), t.clone());
+                let value: String = self.get_expression(value);
+                self.locals.push(name.clone());
+                format!("{} {} = {};\n", self.get_type(t), name, value)
+            }
+            Statement::Let(name, None, value, _) => {
+                let value: String = self.get_expression(value);
+                self.locals.push(name.clone());
+                format!("auto {} = {};\n", name, value)
+            }
+            // The value is kept in a temporary and the pattern's names refer to its parts.
+            Statement::Destructure(pattern, t, value, _) => {
+                let temporary: String = format!("__let{}", self.temporaries);
+                self.temporaries += 1;
+                let t: String = t.map(|t| self.get_type(t)).unwrap_or("auto".to_string());
+                let mut code: String = format!("const {} {} = {};\n", t, temporary, self.get_expression(value));
+                for (name, path) in self.get_pattern_bindings(pattern, temporary) {
+                    code.push_str(&format!("auto {} = {};\n", name, path));
+                    self.locals.push(name);
+                }
+                code
+            }
+            Statement::Expression(expression, _) => {
+                format!("{};\n", self.get_expression(expression))
+            }
             _ => String::new()
         }
     }
@@ -332,8 +429,10 @@ impl Codegen {
             }
             Statement::Procedure(name, _, args, return_type, expression, _) => {
                 let mut args_string = String::new();
+                let scope: usize = self.locals.len();
                 for (name, t) in args.iter() {
                     self.types.insert(name.clone(), t.clone());
+                    self.locals.push(name.clone());
                     args_string.push_str(&format!("{} {}, ", self.get_type(t.clone()), name));
                 }
                 if args.len() > 0 {
@@ -341,10 +440,15 @@ impl Codegen {
                     args_string.pop();
                 }
                 code.push_str(&format!("{} {}{}({}) {{\n", self.get_type(return_type.clone()), if self.current_class.is_some() { format!("{}::", self.current_class.clone().unwrap()) } else { String::new() }, name, args_string));
-                if let Expression::Match(_, _, _, _) = expression {
-                    code.push_str(self.get_expression(expression).as_str());
-                    code.push_str(";\n");
-                } else if let Type::Unit(_) = return_type.clone() {
+                // A block body's bindings become locals ahead of the result.
+                let (statements, expression): (Vec<Statement>, Expression) = match expression {
+                    Expression::Block(statements, result, _) => (statements, *result),
+                    expression => (vec![], expression),
+                };
+                for statement in statements.iter() {
+                    code.push_str(&self.get_statement(statement.clone()));
+                }
+                if let Type::Unit(_) = return_type.clone() {
                     code.push_str(&format!("{};\n", self.get_expression(expression)));
                 } else {
                     code.push_str(&format!("return {};\n", self.get_expression(expression)));
@@ -353,11 +457,19 @@ impl Codegen {
                 for (name, _) in args.iter() {
                     self.types.remove(name);
                 }
+                for statement in statements.iter() {
+                    if let Statement::Let(name, _, _, _) = statement {
+                        self.types.remove(name);
+                    }
+                }
+                self.locals.truncate(scope);
             }
             Statement::GenericProcedure(name, _, generics, args, return_type, expression, _) => {
                 let mut args_string = String::new();
+                let scope: usize = self.locals.len();
                 for (name, t) in args.iter() {
                     self.types.insert(name.clone(), t.clone());
+                    self.locals.push(name.clone());
                     args_string.push_str(&format!("{} {}, ", self.get_type(t.clone()), name));
                 }
                 if args.len() > 0 {
@@ -382,9 +494,15 @@ impl Codegen {
                 generics_string.pop();
                 code.push_str(&format!("template <{}>\n", generics_string));
                 code.push_str(&format!("{} {}{}({}) {{\n", self.get_type(return_type.clone()), if self.current_class.is_some() { format!("{}::", self.current_class.clone().unwrap()) } else { String::new() }, name, args_string));
-                if let Expression::Match(_, _, _, _) = expression {
-
-                } else if let Type::Unit(_) = return_type.clone() {
+                // A block body's bindings become locals ahead of the result.
+                let (statements, expression): (Vec<Statement>, Expression) = match expression {
+                    Expression::Block(statements, result, _) => (statements, *result),
+                    expression => (vec![], expression),
+                };
+                for statement in statements.iter() {
+                    code.push_str(&self.get_statement(statement.clone()));
+                }
+                if let Type::Unit(_) = return_type.clone() {
                     code.push_str(&format!("{};\n", self.get_expression(expression)));
                 } else {
                     code.push_str(&format!("return {};\n", self.get_expression(expression)));
@@ -393,11 +511,26 @@ impl Codegen {
                 for (name, _) in args.iter() {
                     self.types.remove(name);
                 }
+                for statement in statements.iter() {
+                    if let Statement::Let(name, _, _, _) = statement {
+                        self.types.remove(name);
+                    }
+                }
+                self.locals.truncate(scope);
             }
             Statement::Expression(Expression::Cpp(cpp, _), _) => {
-                code.push_str(cpp.replace("\\\"", "\"").as_str());
-                code.push_str("\n");
+                // `#include` lines were hoisted to the top of the file, everything else stays in place.
+                let rest: String = cpp.split_inclusive('\n').filter(|line| !is_include(line)).collect();
+                if !rest.is_empty() {
+                    code.push_str(&rest);
+                    if !rest.ends_with('\n') {
+                        code.push_str("\n");
+                    }
+                }
             },
+            Statement::Documented(_, statement, _) => {
+                code.push_str(&self.get_cpp_statement(*statement));
+            }
             _ => {}
         }
         code
@@ -431,6 +564,7 @@ impl Codegen {
             }
             Statement::DataEnum(name, _, variants, _) => {
                 self.enums.push(name.clone());
+                let enum_name: String = name.clone();
                 code.push_str(&format!("namespace {}_Variants {{\n", name));
                 let mut value_names = Vec::new();
                 for variant in variants {
@@ -438,7 +572,8 @@ impl Codegen {
                         EnumVarient::Tuple(name, types, _) => {
                             let mut args = String::new();
                             for (i, t) in types.iter().enumerate() {
-                                args.push_str(&format!("{} __{}; ", self.get_type(t.clone()), i));
+                                let owner: String = format!("{}.{}", enum_name, name);
+                                args.push_str(&format!("{} __{}; ", self.get_field_type(&owner, &format!("__{}", i), t.clone()), i));
                             }
                             code.push_str(&format!("struct {} {{ {}}};\n", name, args));
                             value_names.push(name);
@@ -450,29 +585,26 @@ impl Codegen {
                     }
                 }
                 code.push_str("}\n");
-                let names = value_names.iter().map(|x| format!("{}_Variants::{}", name, x)).collect::<Vec<String>>().join(", ");
-                code.push_str(&format!("using {} = Enum<{}>;\n", name, names));
+                // A recursive enum's alias was already declared with the forward declarations.
+                if !self.recursive.contains(&name) {
+                    let names = value_names.iter().map(|x| format!("{}_Variants::{}", name, x)).collect::<Vec<String>>().join(", ");
+                    code.push_str(&format!("using {} = Enum<{}>;\n", name, names));
+                }
             }
             Statement::GenericDataEnum(name, _, generics, variants, _) => {
                 self.enums.push(name.clone());
+                let enum_name: String = name.clone();
                 code.push_str(&format!("namespace {}_Variants {{\n", name));
                 let mut value_names = Vec::new();
                 for variant in variants {
                     match variant {
                         EnumVarient::Tuple(name, types, _) => {
                             let mut args = String::new();
-                            let mut generic_names = Vec::new();
-                            for t in types.iter() {
-                                match t {
-                                    Type::Generic(generic, _) => {
-                                        generic_names.push(generic.clone());
-                                    }
-                                    _ => {}
-                                }
-                            }
-                            code.push_str(&format!("template <{}>\n", generic_names.iter().map(|x| format!("typename {}", x)).collect::<Vec<String>>().join(", ")));
+                            // A variant is a template over the enum's type parameters its fields use.
+                            code.push_str(&self.get_parameters_template(&self.get_variant_parameters(&enum_name, &name)));
                             for (i, t) in types.iter().enumerate() {
-                                args.push_str(&format!("{} __{}; ", self.get_type(t.clone()), i));
+                                let owner: String = format!("{}.{}", enum_name, name);
+                                args.push_str(&format!("{} __{}; ", self.get_field_type(&owner, &format!("__{}", i), t.clone()), i));
                             }
                             code.push_str(&format!("struct {} {{ {}}};\n", name, args));
                             value_names.push(name);
@@ -484,30 +616,17 @@ impl Codegen {
                     }
                 }
                 code.push_str("}\n");
-                let mut generics_string = String::new();
-                for generic in generics {
-                    match generic.1 {
-                        GenericType::Extends => {
-                            generics_string.push_str(&format!("typename {} = ", self.get_type(generic.0)));
-                            let extends_type = self.get_type(generic.2.clone()[0].clone());
-                            generics_string.push_str(&format!("{}, ", extends_type));
-                        }
-                        GenericType::Implements => panic!("Implementing generics is not supported yet"),
-                        GenericType::None => {
-                            generics_string.push_str(&format!("typename {}, ", self.get_type(generic.0)));
-                        }
-                    }
+                // A recursive enum's alias was already declared with the forward declarations.
+                if !self.recursive.contains(&name) {
+                    code.push_str(&self.get_template(&generics));
+                    let names = value_names.iter().map(|x| self.get_variant_path(&name, x, &HashMap::new())).collect::<Vec<String>>().join(", ");
+                    code.push_str(&format!("using {} = Enum<{}>;\n", name, names));
                 }
-                generics_string.pop();
-                generics_string.pop();
-                code.push_str(&format!("template <{}>\n", generics_string));
-                let names = value_names.iter().map(|x| format!("{}_Variants::{}", name, x)).collect::<Vec<String>>().join(", ");
-                code.push_str(&format!("using {} = Enum<{}>;\n", name, names));
             }
             Statement::DataStruct(name, _, fields, _) => {
                 code.push_str(&format!("struct {} {{\n", name));
                 for field in fields {
-                    code.push_str(&format!("{} {};\n", self.get_type(field.1), field.0));
+                    code.push_str(&format!("{} {};\n", self.get_field_type(&name, &field.0, field.1), field.0));
                 }
                 code.push_str("};\n");
             }
@@ -531,36 +650,26 @@ impl Codegen {
                 code.push_str(&format!("template <{}>\n", generics_string));
                 code.push_str(&format!("struct {} {{\n", name));
                 for field in fields {
-                    code.push_str(&format!("{} {};\n", self.get_type(field.1), field.0));
+                    code.push_str(&format!("{} {};\n", self.get_field_type(&name, &field.0, field.1), field.0));
                 }
                 code.push_str("};\n");
             }
             Statement::Object(name, _, parents, parameters, members, _) => {
                 self.current_class = Some(name.clone());
-                code.push_str(&format!("class {} ", name));
-                for parent in parents.iter() {
-                    code.push_str(format!("public {}, ", self.get_type(parent.clone())).as_str());
-                }
-                if parents.len() > 0 {
-                    code.pop();
-                    code.pop();
-                }
-                code.push_str("{\n");
+                code.push_str(&format!("class {} {{\n", self.get_class_bases(&name, &parents, &members)));
                 let mut public_members: Vec<Statement> = Vec::new();
                 let mut private_members: Vec<Statement> = Vec::new();
-                let mut traits: Vec<Statement> = Vec::new();
                 for member in members {
-                    match member {
-                        Statement::Procedure(_, ref flags, _, _, _, _) => {
+                    match member.undocumented() {
+                        Statement::Procedure(_, flags, _, _, _, _) => {
                             if flags.contains(&AccessFlag::Public) {
                                 public_members.push(member);
                             } else {
                                 private_members.push(member);
                             }
                         }
-                        Statement::Of(_, _) => {
-                            traits.push(member);
-                        }
+                        // Traits are base classes, see `get_class_bases`.
+                        Statement::Of(_, _) => {}
                         _ => panic!("Invalid member"),
                     }
                 }
@@ -593,10 +702,6 @@ impl Codegen {
                     code.push_str(&self.get_header_statement(member));
                 }
 
-                for trait_ in traits {
-                    code.push_str(&self.get_header_statement(trait_));
-                }
-
                 if private_members.len() > 0 || parameters.len() > 0 {
                     code.push_str("private:\n");
                     for parameter in parameters.iter() {
@@ -629,26 +734,19 @@ impl Codegen {
                 generics_string.pop();
                 generics_string.pop();
                 code.push_str(&format!("template <{}>\n", generics_string));
-                code.push_str(&format!("class {} ", name));
-                for parent in parents.iter() {
-                    code.push_str(format!("public {}, ", self.get_type(parent.clone())).as_str());
-                }
-                if parents.len() > 0 {
-                    code.pop();
-                    code.pop();
-                }
-                code.push_str("{\n");
+                code.push_str(&format!("class {} {{\n", self.get_class_bases(&name, &parents, &members)));
                 let mut public_members = Vec::new();
                 let mut private_members = Vec::new();
                 for member in members {
-                    match member {
-                        Statement::Procedure(_, ref flags, _, _, _, _) => {
+                    match member.undocumented() {
+                        Statement::Procedure(_, flags, _, _, _, _) => {
                             if flags.contains(&AccessFlag::Public) {
                                 public_members.push(member);
                             } else {
                                 private_members.push(member);
                             }
                         }
+                        Statement::Of(_, _) => {}
                         _ => panic!("Invalid member"),
                     }
                 }
@@ -673,6 +771,11 @@ impl Codegen {
             Statement::Const(name, _, t, value, _) => {
                 code.push_str(&format!("constexpr {} {} = {};\n", self.get_type(t), name, self.get_expression(value)));
             }
+            // A trait's default procedure, defined inline in its class.
+            statement @ Statement::Procedure(..) if self.current_trait.is_some() => {
+                code.push_str("virtual ");
+                code.push_str(&self.get_cpp_statement(statement));
+            }
             Statement::Procedure(name, flags, args, return_type, _, _) => {
                 let mut args_string = String::new();
                 for arg in args {
@@ -683,11 +786,11 @@ impl Codegen {
                     args_string.pop();
                 }
                 if flags.contains(&AccessFlag::Virtual) {
-                    code.push_str(&format!("virtual {} {}{}({}) = 0;\n", self.get_type(return_type), if self.current_class.is_some() { format!("{}::", self.current_class.clone().unwrap()) } else { String::new() }, name, args_string));
+                    code.push_str(&format!("virtual {} {}({}) = 0;\n", self.get_type(return_type), name, args_string));
                 } else if flags.contains(&AccessFlag::Override) {
-                    code.push_str(&format!("virtual {} {}{}({}) override = 0;\n", self.get_type(return_type), if self.current_class.is_some() { format!("{}::", self.current_class.clone().unwrap()) } else { String::new() }, name, args_string));
+                    code.push_str(&format!("virtual {} {}({}) override = 0;\n", self.get_type(return_type), name, args_string));
                 } else {
-                    code.push_str(&format!("{} {}{}({});\n", self.get_type(return_type), if self.current_class.is_some() { format!("{}::", self.current_class.clone().unwrap()) } else { String::new() }, name, args_string));
+                    code.push_str(&format!("{} {}({});\n", self.get_type(return_type), name, args_string));
                 }
             }
             Statement::GenericProcedure(name, flags, generics, args, return_type, _, _) => {
@@ -717,27 +820,97 @@ impl Codegen {
                 }
                 code.push_str(&format!("template <{}>\n", generics_string));
                 if flags.contains(&AccessFlag::Virtual) {
-                    code.push_str(&format!("virtual {} {}{}({}) = 0;\n", self.get_type(return_type), if self.current_class.is_some() { format!("{}::", self.current_class.clone().unwrap()) } else { String::new() }, name, args_string));
+                    code.push_str(&format!("virtual {} {}({}) = 0;\n", self.get_type(return_type), name, args_string));
                 } else if flags.contains(&AccessFlag::Override) {
-                    code.push_str(&format!("virtual {} {}{}({}) override = 0;\n", self.get_type(return_type), if self.current_class.is_some() { format!("{}::", self.current_class.clone().unwrap()) } else { String::new() }, name, args_string));
+                    code.push_str(&format!("virtual {} {}({}) override = 0;\n", self.get_type(return_type), name, args_string));
                 } else {
-                    code.push_str(&format!("{} {}{}({});\n", self.get_type(return_type), if self.current_class.is_some() { format!("{}::", self.current_class.clone().unwrap()) } else { String::new() }, name, args_string));
+                    code.push_str(&format!("{} {}({});\n", self.get_type(return_type), name, args_string));
                 }
             }
-            Statement::Of(trait_type, _) => {
-                code.push_str(&self.get_type(trait_type));
+            Statement::Trait(name, _, members, _) => {
+                code.push_str(&self.get_trait(name, members));
+            }
+            Statement::GenericTrait(name, _, generics, members, _) => {
+                let mut generics_string = String::new();
+                for generic in generics {
+                    generics_string.push_str(&format!("typename {}, ", self.get_type(generic.0)));
+                }
+                generics_string.pop();
+                generics_string.pop();
+                code.push_str(&format!("template <{}>\n", generics_string));
+                code.push_str(&self.get_trait(name, members));
+            }
+            Statement::Signature(name, _, args, return_type, _) => {
+                let mut args_string = String::new();
+                for arg in args {
+                    args_string.push_str(&format!("{} {}, ", self.get_type(arg.1), arg.0));
+                }
+                if args_string.len() > 0 {
+                    args_string.pop();
+                    args_string.pop();
+                }
+                code.push_str(&format!("virtual {} {}({}) = 0;\n", self.get_type(return_type), name, args_string));
+            }
+            Statement::Documented(doc, statement, _) => {
+                for line in doc.lines() {
+                    if line.is_empty() {
+                        code.push_str("///\n");
+                    } else {
+                        code.push_str(&format!("/// {}\n", line));
+                    }
+                }
+                code.push_str(&self.get_header_statement(*statement));
             }
             _ => {}
         }
         code
     }
 
+    // A trait is an abstract base class: signatures are pure virtual and default procedures are
+    // virtual members defined inline, so generic traits work as class templates.
+    fn get_trait(&mut self, name: String, members: Vec<Statement>) -> String {
+        let mut code: String = String::new();
+        code.push_str(&format!("class {} {{\n", name));
+        code.push_str("public:\n");
+        code.push_str(&format!("virtual ~{}() = default;\n", name));
+        self.current_trait = Some(name);
+        for member in members {
+            code.push_str(&self.get_header_statement(member));
+        }
+        self.current_trait = None;
+        code.push_str("};\n");
+        code
+    }
+
+    // `Name : public Parent, public Trait` for an object's class head. A generic trait named
+    // without type arguments is implemented for the object itself, like `Show<Point>`.
+    fn get_class_bases(&self, name: &str, parents: &[Type], members: &[Statement]) -> String {
+        let mut bases: Vec<String> = parents.iter().map(|parent| self.get_type(parent.clone())).collect();
+        for member in members.iter() {
+            if let Statement::Of(trait_type, _) = member.undocumented() {
+                let is_generic: bool = matches!(trait_type, Type::Unknown(trait_name, _) if self.statements.iter().any(|statement| {
+                    matches!(statement.undocumented(), Statement::GenericTrait(n, _, generics, _, _) if n == trait_name && generics.len() == 1)
+                }));
+                if is_generic {
+                    bases.push(format!("{}<{}>", self.get_type(trait_type.clone()), name));
+                } else {
+                    bases.push(self.get_type(trait_type.clone()));
+                }
+            }
+        }
+        if bases.is_empty() {
+            return name.to_string();
+        }
+        let bases: Vec<String> = bases.iter().map(|base| format!("public {}", base)).collect();
+        format!("{} : {}", name, bases.join(", "))
+    }
+
     fn get_module_path(&self, expr: Expression) -> String {
         match expr {
             Expression::Member(expression, member, _) => {
                 let mut module_path: String = self.get_module_path(*expression);
                 module_path.push_str(".");
-                module_path.push_str(&self.get_module_path(*member));
+                module_path.push_str(&member);
                 module_path
             }
             Expression::Variable(id, _) => id,
@@ -749,6 +922,7 @@ impl Codegen {
         match t {
             Type::Unit(_) => "void".to_string(),
             Type::Int(_) => "int".to_string(),
+            Type::Float(_) => "double".to_string(),
             Type::Char(_) => "char".to_string(),
             Type::Bool(_) => "bool".to_string(),
             Type::GenericParameter(name, _) => name,
@@ -771,6 +945,7 @@ impl Codegen {
             Type::Optional(inner, _) => format!("Optional<{}>", self.get_type(*inner)),
             Type::Array(inner, _) => format!("List<{}>", self.get_type(*inner)),
             Type::Function(types, inner, _) => format!("Function<{}({})>", self.get_type(*inner), types.iter().map(|x| self.get_type(x.clone())).collect::<Vec<String>>().join(", ")),
+            Type::Tuple(types, _) => format!("std::tuple<{}>", types.iter().map(|x| self.get_type(x.clone())).collect::<Vec<String>>().join(", ")),
             Type::Unknown(name, _) => {
                 if name == "cstring" {
                     "const char*".to_string()
@@ -781,101 +956,291 @@ impl Codegen {
         }
     }
 
+    // `if (...) { return ...; } else ...` with `else if` chains kept flat.
+    fn get_if_statement(&mut self, condition: Expression, then_branch: Expression, else_branch: Expression) -> String {
+        let mut code: String = format!("if ({}) {{\n", self.get_expression(condition));
+        code.push_str(&self.get_branch(then_branch));
+        code.push_str("} else ");
+        if let Expression::If(condition, then_branch, else_branch, _) = else_branch {
+            code.push_str(&self.get_if_statement(*condition, *then_branch, *else_branch));
+        } else {
+            code.push_str("{\n");
+            code.push_str(&self.get_branch(else_branch));
+            code.push_str("}\n");
+        }
+        code
+    }
+    fn get_branch(&mut self, branch: Expression) -> String {
+        let scope: usize = self.locals.len();
+        let mut code: String = String::new();
+        let result: Expression = match branch {
+            Expression::Block(statements, result, _) => {
+                for statement in statements {
+                    code.push_str(&self.get_statement(statement));
+                }
+                *result
+            }
+            branch => branch,
+        };
+        code.push_str(&format!("return {};\n", self.get_expression(result)));
+        self.locals.truncate(scope);
+        code
+    }
+
+    // Operands of operators are parenthesized when they are operators themselves, so the C++
+    // keeps the grouping of the Real AST instead of relying on matching precedence rules.
+    fn get_operand(&mut self, expr: Expression) -> String {
+        match expr {
+            Expression::Binary(_, _, _, _) | Expression::Unary(_, _, _) | Expression::If(_, _, _, _) |
+            Expression::Lambda(_, _, _) => format!("({})", self.get_expression(expr)),
+            _ => self.get_expression(expr),
+        }
+    }
+    fn get_arguments(&mut self, args: Vec<Expression>) -> String {
+        args.into_iter().map(|x| self.get_expression(x)).collect::<Vec<String>>().join(", ")
+    }
+    // Explicit type arguments of a call, like `<int, String>`.
+    fn get_type_arguments(&self, types: Vec<Type>) -> String {
+        if types.is_empty() {
+            return String::new();
+        }
+        format!("<{}>", types.into_iter().map(|t| self.get_type(t)).collect::<Vec<String>>().join(", "))
+    }
+    // The type parameters of a generic data enum that the fields of `variant` mention, which
+    // makes the variant a template over them.
+    pub(crate) fn get_variant_parameters(&self, enum_name: &str, variant: &str) -> Vec<String> {
+        let types: Vec<Type> = self.variants.get(enum_name).into_iter().flatten().find_map(|v| match v {
+            EnumVarient::Tuple(name, types, _) if name == variant => Some(types.clone()),
+            _ => None,
+        }).unwrap_or_default();
+        self.generics.get(enum_name).into_iter().flatten()
+            .filter(|parameter| types.iter().any(|t| mentions(t, parameter)))
+            .cloned()
+            .collect()
+    }
+    // `Enum_Variants::Variant`, with the types in `arguments` for the variant's type parameters.
+    // A parameter without one is passed on as it is.
+    pub(crate) fn get_variant_path(&self, enum_name: &str, variant: &str, arguments: &HashMap<String, Type>) -> String {
+        let parameters: Vec<String> = self.get_variant_parameters(enum_name, variant);
+        if parameters.is_empty() {
+            return format!("{}_Variants::{}", enum_name, variant);
+        }
+        let arguments: Vec<String> = parameters.into_iter().map(|parameter| match arguments.get(&parameter) {
+            Some(t) => self.get_type(t.clone()),
+            None => parameter,
+        }).collect();
+        format!("{}_Variants::{}<{}>", enum_name, variant, arguments.join(", "))
+    }
+    // `template <typename T, typename E = Error>` for a generic data type's parameters.
+    pub(crate) fn get_template(&self, generics: &[(Type, GenericType, Vec<Type>)]) -> String {
+        let parameters: Vec<String> = generics.iter().map(|(t, generic_type, bounds)| match generic_type {
+            GenericType::Extends => format!("typename {} = {}", self.get_type(t.clone()), self.get_type(bounds[0].clone())),
+            GenericType::Implements => panic!("Implementing generics is not supported yet"),
+            GenericType::None => format!("typename {}", self.get_type(t.clone())),
+        }).collect();
+        format!("template <{}>\n", parameters.join(", "))
+    }
+    // `template <typename T>` for `parameters`, nothing without any.
+    pub(crate) fn get_parameters_template(&self, parameters: &[String]) -> String {
+        if parameters.is_empty() {
+            return String::new();
+        }
+        format!("template <{}>\n", parameters.iter().map(|x| format!("typename {}", x)).collect::<Vec<String>>().join(", "))
+    }
+
     fn get_expression(&mut self, expr: Expression) -> String {
         match expr {
             Expression::Variable(id, _) => id,
-            Expression::Integer(value, _) => value.to_string(),
-            Expression::String(value, _) => format!("String::from_cstr(\"{}\")", value),
+            Expression::Bool(value, _) => value.to_string(),
+            Expression::Integer(value, None, _) => value.to_string(),
+            Expression::Integer(value, Some(suffix), _) => {
+                let cpp_type: String = format!("std::{}int{}_t", if suffix.is_signed() { "" } else { "u" }, &suffix.name()[1..]);
+                format!("static_cast<{}>({}{})", cpp_type, value, if suffix.is_signed() { "" } else { "U" })
+            }
+            Expression::Float(value, _) => format!("{:?}", value),
+            Expression::String(value, _) => format!("String::from_cstr(\"{}\")", escape(&value, '"')),
+            Expression::Interpolated(parts, _) => {
+                let mut strings: Vec<String> = vec![];
+                for part in parts {
+                    match part {
+                        StringPart::Text(text) => strings.push(format!("String::from_cstr(\"{}\")", escape(&text, '"'))),
+                        StringPart::Expression(expression) => strings.push(format!("to_string({})", self.get_expression(expression))),
+                    }
+                }
+                format!("String::concat({{ {} }})", strings.join(", "))
+            }
+            Expression::Char(value, _) => format!("'{}'", escape(&value.to_string(), '\'')),
+            // `Enum.Variant` constructs a variant without fields.
             Expression::Member(expression, member, _) => {
-                if let Expression::Variable(id, _) = *expression.clone() {
-                    if let Expression::Call(callee, args, _) = *member.clone() {
-                        if self.enums.contains(&id) {
-                            let mut expr: String = format!("{} {{ {}_Variants::{} {{ ", id, id, callee);
-                            expr.push_str(&args.iter().map(|x| self.get_expression(x.clone())).collect::<Vec<String>>().join(", "));
-                            expr.push_str(" } }");
-                            return expr;
-                        }
+                if let Expression::Variable(id, _) = expression.as_ref() {
+                    if self.variants.contains_key(id) {
+                        return format!("{} {{ {}_Variants::{} {{}} }}", id, id, member);
                     }
                 }
-                let mut expr: String = self.get_expression(*expression);
-                expr.push_str(".");
-                expr.push_str(&self.get_expression(*member));
-                expr
+                let owner: Option<String> = match expression.as_ref() {
+                    Expression::Variable(id, _) => self.types.get(id).and_then(|t| match t {
+                        Type::DataStruct(name, _) | Type::Unknown(name, _) => Some(name.clone()),
+                        _ => None,
+                    }),
+                    _ => None,
+                };
+                let value: String = self.get_operand(*expression);
+                self.get_member(owner.as_deref(), &value, &member)
             }
-            Expression::Call(name, args, _) => {
-                let mut expr: String = name;
-                expr.push_str("(");
-                expr.push_str(&args.iter().map(|x| self.get_expression(x.clone())).collect::<Vec<String>>().join(", "));
-                expr.push_str(")");
-                expr
+            Expression::Call(callee, types, args, _) => {
+                let callee: String = self.get_operand(*callee);
+                format!("{}{}({})", callee, self.get_type_arguments(types), self.get_arguments(args))
             }
-            Expression::Match(expression, cases, else_case, _) => {
-                let name: String = self.get_expression(*expression);
-                let mut expr: String = format!("std::visit([this](auto&& {}) {{\n", name);
-                expr.push_str(&format!("using T = std::decay_t<decltype({})>;\n", name));
-                for case in cases {
-                    let mut case_expr: String = String::new();
-                    if let Expression::Call(callee, args, _) = case.condition[0].clone() {
-                        let t: Type = self.types.get(&name).unwrap().clone();
-                        let case_name: String = format!("{}_Variants::{}", self.get_type(t), callee);
-                        case_expr.push_str(&format!("if constexpr (std::is_same_v<T, {}>) {{\n", case_name));
-                        for (i, arg) in args.iter().enumerate() {
-                            if let Expression::Variable(id, _) = arg {
-                                case_expr.push_str(&format!("auto {} = {}.__{};\n", id, name, i));
-                            } else {
-                                panic!("Invalid match case");
-                            }
-                        }
-                    } else if let Expression::Variable(id, _) = case.condition[0].clone() {
-                        let t: Type = self.types.get(&name).unwrap().clone();
-                        let case_name: String = format!("{}_Variants::{}", self.get_type(t), id);
-                        case_expr.push_str(&format!("if constexpr (std::is_same_v<T, {}>) {{\n", case_name));
-                    } else {
-                        panic!("Invalid match case");
+            // Data structs are aggregates, so the fields go in declaration order whatever order they were given in.
+            Expression::Construct(name, types, fields, _) => {
+                let declared: Vec<(String, Type)> = self.structs.get(&name).cloned().unwrap_or_default();
+                let mut values: Vec<Option<String>> = vec![None; declared.len()];
+                for (i, (field, value)) in fields.into_iter().enumerate() {
+                    let position: Option<usize> = match field {
+                        Some(field) => declared.iter().position(|(name, _)| *name == field),
+                        None => Some(i),
+                    };
+                    if let Some(position) = position.filter(|position| *position < values.len()) {
+                        values[position] = Some(self.get_expression(value));
                     }
-                    if case.body.len() == 1 {
-                        if let Statement::Expression(expression, _) = case.body[0].clone() {
-                            if let Expression::Call(_, _, _) = expression {
-                                case_expr.push_str(&format!("{};\n", self.get_expression(expression)));
-                                case_expr.push_str("return;\n");
-                            } else {
-                                case_expr.push_str(&format!("return {};\n", self.get_expression(expression)));
-                            }
-                        }
-                    } else {
-                        case_expr.push_str(&case.body.iter().map(|x| self.get_statement(x.clone())).collect::<Vec<String>>().join(""));
+                }
+                let values: Vec<String> = values.into_iter().map(|value| value.unwrap_or_default()).collect();
+                format!("{}{} {{ {} }}", name, self.get_type_arguments(types), values.join(", "))
+            }
+            Expression::With(value, fields, _) => {
+                let mut expr: String = format!("[&]() {{\nauto __with = {};\n", self.get_expression(*value));
+                for (field, new_value) in fields {
+                    expr.push_str(&format!("__with.{} = {};\n", field, self.get_expression(new_value)));
+                }
+                expr.push_str("return __with;\n}()");
+                expr
+            }
+            // `Enum.Variant(a, b)` constructs a variant, anything else is a C++ member function call.
+            Expression::MethodCall(receiver, method, types, args, _) => {
+                if let Expression::Variable(id, _) = receiver.as_ref() {
+                    if self.variants.contains_key(id) {
+                        return format!("{} {{ {}_Variants::{} {{ {} }} }}", id, id, method, self.get_arguments(args));
                     }
-                    case_expr.push_str("}\n");
-                    expr.push_str(&case_expr);
                 }
-                if let Some(else_case) = else_case {
-                    if else_case.body.len() == 1 {
-                        if let Statement::Expression(expression, _) = else_case.body[0].clone() {
-                            expr.push_str(&format!("return {};\n", self.get_expression(expression)));
-                        }
-                    } else {
-                        expr.push_str(&else_case.body.iter().map(|x| self.get_statement(x.clone())).collect::<Vec<String>>().join(""));
+                let receiver: String = self.get_operand(*receiver);
+                format!("{}.{}{}({})", receiver, method, self.get_type_arguments(types), self.get_arguments(args))
+            }
+            Expression::Match(expression, cases, else_case, span) => self.get_match(*expression, cases, else_case, span),
+            // A `let ... in` inside an expression is an immediately invoked lambda.
+            Expression::Block(statements, result, _) => {
+                let scope: usize = self.locals.len();
+                let mut expr: String = String::from("[&]() {\n");
+                for statement in statements.iter() {
+                    expr.push_str(&self.get_statement(statement.clone()));
+                }
+                expr.push_str(&format!("return {};\n}}()", self.get_expression(*result)));
+                for statement in statements.iter() {
+                    if let Statement::Let(name, _, _, _) = statement {
+                        self.types.remove(name);
                     }
                 }
-                expr.push_str("}, ");
-                expr.push_str(&name);
-                expr.push_str(")");
+                self.locals.truncate(scope);
                 expr
             }
+            // Branches that are plain expressions make a ternary, ones with `let` bindings need
+            // statements, so the whole `if` becomes an immediately invoked lambda.
+            Expression::If(condition, then_branch, else_branch, _) => {
+                if is_simple_branch(&then_branch) && is_simple_branch(&else_branch) {
+                    let condition: String = self.get_operand(*condition);
+                    let then_branch: String = self.get_operand(*then_branch);
+                    let else_branch: String = self.get_operand(*else_branch);
+                    format!("{} ? {} : {}", condition, then_branch, else_branch)
+                } else {
+                    format!("[&]() {{\n{}}}()", self.get_if_statement(*condition, *then_branch, *else_branch))
+                }
+            }
+            // Lambdas capture, by value, exactly the locals their body uses.
+            Expression::Lambda(parameters, body, _) => {
+                let mut bound: Vec<String> = parameters.iter().map(|(name, _)| name.clone()).collect();
+                let mut free: Vec<String> = vec![];
+                free_variables(&body, &mut bound, &mut free);
+                let captures: Vec<String> = free.into_iter().filter(|name| self.locals.contains(name)).collect();
+                let mut parameters_string: Vec<String> = vec![];
+                for (name, t) in parameters.iter() {
+                    match t {
+                        Some(t) => parameters_string.push(format!("{} {}", self.get_type(t.clone()), name)),
+                        None => parameters_string.push(format!("auto {}", name)),
+                    }
+                }
+                let scope: usize = self.locals.len();
+                self.locals.extend(parameters.iter().map(|(name, _)| name.clone()));
+                let body: String = self.get_expression(*body);
+                self.locals.truncate(scope);
+                format!("[{}]({}) {{ return {}; }}", captures.join(", "), parameters_string.join(", "), body)
+            }
             Expression::Binary(left, right, op, _) => {
-                let mut expr: String = self.get_expression(*left);
+                let mut expr: String = self.get_operand(*left);
                 expr.push_str(&format!(" {} ", match op {
                     TokenKind::Plus => "+",
                     TokenKind::Minus => "-",
                     TokenKind::Asterisk => "*",
                     TokenKind::Slash => "/",
                     TokenKind::Percent => "%",
+                    TokenKind::EqualEqual => "==",
+                    TokenKind::BangEqual => "!=",
+                    TokenKind::Less => "<",
+                    TokenKind::LessEqual => "<=",
+                    TokenKind::Greater => ">",
+                    TokenKind::GreaterEqual => ">=",
+                    TokenKind::AmpersandAmpersand => "&&",
+                    TokenKind::PipePipe => "||",
                     _ => panic!("Invalid binary operator"),
                 }));
-                expr.push_str(&self.get_expression(*right));
+                expr.push_str(&self.get_operand(*right));
+                expr
+            }
+            Expression::Unary(operand, op, _) => {
+                // `2147483648` doesn't fit an `int`, so the smallest `Int` is written as `(-2147483647 - 1)`.
+                if let (TokenKind::Minus, Expression::Integer(value, suffix, _)) = (&op, operand.as_ref()) {
+                    if *value as u128 > integer_max(*suffix) {
+                        let minimum: String = format!("(-{} - 1)", value - 1);
+                        return match suffix {
+                            Some(suffix) => format!("static_cast<std::int{}_t>{}", &suffix.name()[1..], minimum),
+                            None => minimum,
+                        };
+                    }
+                }
+                let mut expr: String = match op {
+                    TokenKind::Bang => "!",
+                    TokenKind::Minus => "-",
+                    _ => panic!("Invalid unary operator"),
+                }.to_string();
+                expr.push_str(&self.get_operand(*operand));
                 expr
             }
-            Expression::Cpp(string, _) => string.replace("\\\"", "\""),
+            Expression::Unsafe(operand, _) => {
+                let in_unsafe: bool = self.in_unsafe;
+                self.in_unsafe = true;
+                let expr: String = self.get_expression(*operand);
+                self.in_unsafe = in_unsafe;
+                expr
+            }
+            // `at` checks bounds for lists and gives an optional for maps.
+            Expression::Index(value, index, _) => {
+                let value: String = self.get_operand(*value);
+                let index: String = self.get_expression(*index);
+                if self.in_unsafe {
+                    format!("{}[{}]", value, index)
+                } else {
+                    format!("{}.at({})", value, index)
+                }
+            }
+            Expression::Slice(value, start, end, _) => {
+                let value: String = self.get_operand(*value);
+                let method: &str = if self.in_unsafe { "unchecked_slice" } else { "slice" };
+                let start: String = start.map(|start| self.get_expression(*start)).unwrap_or("0".to_string());
+                match end {
+                    Some(end) => format!("{}.{}({}, {})", value, method, start, self.get_expression(*end)),
+                    None => format!("{}.{}({})", value, method, start),
+                }
+            }
+            Expression::Tuple(items, _) => format!("std::make_tuple({})", self.get_arguments(items)),
+            Expression::Cpp(string, _) => string,
             Expression::List(items, _) => format!("List<{}>::from_array(new {}[{}]{{ {} }}, {})", self.get_type(items[0].get_type()), self.get_type(items[0].get_type()), items.len(), items.iter().map(|x| self.get_expression(x.clone())).collect::<Vec<String>>().join(", "), items.len()),
             Expression::Map(map, _) => {
                 let mut keys: Vec<Expression> = vec![];
@@ -891,4 +1256,162 @@ impl Codegen {
             _ => panic!("unhandled expression {:?}", expr)
         }
     }
+}
+
+fn is_simple_branch(branch: &Expression) -> bool {
+    match branch {
+        Expression::Block(..) => false,
+        Expression::If(_, then_branch, else_branch, _) => is_simple_branch(then_branch) && is_simple_branch(else_branch),
+        _ => true,
+    }
+}
+
+// Collects the variables `expression` uses that aren't `bound` inside it, in order of first use.
+fn free_variables(expression: &Expression, bound: &mut Vec<String>, free: &mut Vec<String>) {
+    let mut use_variable = |name: &String, bound: &Vec<String>| {
+        if !bound.contains(name) && !free.contains(name) {
+            free.push(name.clone());
+        }
+    };
+    match expression {
+        Expression::Variable(name, _) => use_variable(name, bound),
+        Expression::Call(callee, _, args, _) | Expression::MethodCall(callee, _, _, args, _) => {
+            free_variables(callee, bound, free);
+            for arg in args.iter() {
+                free_variables(arg, bound, free);
+            }
+        }
+        Expression::Member(expression, _, _) => free_variables(expression, bound, free),
+        Expression::Construct(_, _, fields, _) => {
+            for (_, value) in fields.iter() {
+                free_variables(value, bound, free);
+            }
+        }
+        Expression::With(value, fields, _) => {
+            free_variables(value, bound, free);
+            for (_, new_value) in fields.iter() {
+                free_variables(new_value, bound, free);
+            }
+        }
+        Expression::Interpolated(parts, _) => {
+            for part in parts.iter() {
+                if let StringPart::Expression(expression) = part {
+                    free_variables(expression, bound, free);
+                }
+            }
+        }
+        Expression::Binary(left, right, _, _) => {
+            free_variables(left, bound, free);
+            free_variables(right, bound, free);
+        }
+        Expression::Unary(operand, _, _) | Expression::Unsafe(operand, _) => free_variables(operand, bound, free),
+        Expression::Index(value, index, _) => {
+            free_variables(value, bound, free);
+            free_variables(index, bound, free);
+        }
+        Expression::Slice(value, start, end, _) => {
+            free_variables(value, bound, free);
+            for bound_expression in start.iter().chain(end.iter()) {
+                free_variables(bound_expression, bound, free);
+            }
+        }
+        Expression::If(condition, then_branch, else_branch, _) => {
+            free_variables(condition, bound, free);
+            free_variables(then_branch, bound, free);
+            free_variables(else_branch, bound, free);
+        }
+        Expression::List(items, _) | Expression::Tuple(items, _) => {
+            for item in items.iter() {
+                free_variables(item, bound, free);
+            }
+        }
+        Expression::Map(entries, _) => {
+            for (key, value) in entries.iter() {
+                free_variables(key, bound, free);
+                free_variables(value, bound, free);
+            }
+        }
+        // Match cases bind the names in their patterns for the guard and body.
+        Expression::Match(expression, cases, else_case, _) => {
+            free_variables(expression, bound, free);
+            for case in cases.iter().chain(else_case.iter()) {
+                let scope: usize = bound.len();
+                bound.extend(case.pattern.bindings());
+                if let Some(guard) = &case.guard {
+                    free_variables(guard, bound, free);
+                }
+                for statement in case.body.iter() {
+                    statement_free_variables(statement, bound, free);
+                }
+                bound.truncate(scope);
+            }
+        }
+        Expression::Block(statements, result, _) => {
+            let scope: usize = bound.len();
+            for statement in statements.iter() {
+                statement_free_variables(statement, bound, free);
+            }
+            free_variables(result, bound, free);
+            bound.truncate(scope);
+        }
+        Expression::Lambda(parameters, body, _) => {
+            let scope: usize = bound.len();
+            bound.extend(parameters.iter().map(|(name, _)| name.clone()));
+            free_variables(body, bound, free);
+            bound.truncate(scope);
+        }
+        _ => {}
+    }
+}
+// Like `free_variables`, a `let` binds its name for the statements after it.
+fn statement_free_variables(statement: &Statement, bound: &mut Vec<String>, free: &mut Vec<String>) {
+    match statement {
+        Statement::Let(name, _, value, _) => {
+            free_variables(value, bound, free);
+            bound.push(name.clone());
+        }
+        Statement::Destructure(pattern, _, value, _) => {
+            free_variables(value, bound, free);
+            bound.extend(pattern.bindings());
+        }
+        Statement::Expression(expression, _) => free_variables(expression, bound, free),
+        _ => {}
+    }
+}
+
+fn is_include(line: &str) -> bool {
+    line.trim_start().starts_with("#include")
+}
+
+// Whether type parameter `parameter` appears anywhere in `t`.
+fn mentions(t: &Type, parameter: &str) -> bool {
+    match t {
+        Type::Generic(name, _) | Type::GenericParameter(name, _) => name == parameter,
+        Type::GenericType(base, arguments, _) => mentions(base, parameter) || arguments.iter().any(|t| mentions(t, parameter)),
+        Type::Optional(inner, _) | Type::Array(inner, _) => mentions(inner, parameter),
+        Type::Function(arguments, result, _) => arguments.iter().any(|t| mentions(t, parameter)) || mentions(result, parameter),
+        Type::Tuple(types, _) => types.iter().any(|t| mentions(t, parameter)),
+        _ => false,
+    }
+}
+
+// Escapes `value` for a C++ string or character literal delimited by `quote`. Other control
+// characters use octal escapes, which (unlike `\x`) can't swallow the digits that follow.
+fn escape(value: &str, quote: char) -> String {
+    let mut escaped: String = String::new();
+    for c in value.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\\' => escaped.push_str("\\\\"),
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_ascii_control() => escaped.push_str(&format!("\\{:03o}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
 }
\ No newline at end of file
@@ -0,0 +1,515 @@
+use std::collections::HashMap;
+
+use parser::parser::{
+    Statement,
+    Expression,
+    Type,
+    EnumVarient,
+    MatchCase,
+    Pattern,
+};
+
+use lexer::span::Span;
+
+use crate::Codegen;
+
+// A value that still has to be tested against a pattern. The value is a C++ expression like
+// `std::get<Shape_Variants::Rect>(__match).__0`, with its type when that is known.
+type Test = (String, Pattern, Option<Type>);
+
+// One alternative of a case. Only tests of data enum variants and literals are left in
+// `tests`, everything else has already become a binding or was dropped.
+#[derive(Debug, Clone)] struct Row {
+    tests: Vec<Test>,
+    bindings: Vec<(String, String)>,
+    case: usize,
+}
+
+// Matches compile to a decision tree, so every part of the value is tested at most once on
+// the way to a case, following Maranget's "Compiling pattern matching to good decision trees".
+#[derive(Debug, Clone)] enum Decision {
+    // No case matches, which exhaustiveness checking already rules out.
+    Fail,
+    // Binds the names of a case and runs it, when its guard fails matching goes on with the fallback.
+    Leaf(Vec<(String, String)>, usize, Option<Box<Decision>>),
+    // A `switch` on the index of the variant the value holds, with the decision for each index.
+    Switch(String, Vec<(usize, String, Decision)>, Box<Decision>),
+    // Compares the value with each literal in turn.
+    Literals(String, Vec<(String, Decision)>, Box<Decision>),
+}
+
+impl Codegen {
+    pub(crate) fn get_match(&mut self, expression: Expression, cases: Vec<MatchCase>, else_case: Option<MatchCase>, span: Span) -> String {
+        // Without a known type the variants' names decide which data enum is matched.
+        let t: Option<Type> = self.matched_types.get(&span).cloned().or_else(|| match &expression {
+            Expression::Variable(name, _) => self.types.get(name).cloned(),
+            _ => None,
+        });
+        let cases: Vec<MatchCase> = cases.into_iter().chain(else_case).collect();
+        let decision: Decision = self.get_match_decision(t, &cases);
+        // Cases can give values of different C++ types that convert to the match's, like closures.
+        let result: String = match self.result_types.get(&span).cloned() {
+            Some(t) => format!(" -> {}", self.get_type(t)),
+            None => String::new(),
+        };
+        let mut expr: String = format!("[&](){} {{\nconst auto& __match = {};\n", result, self.get_expression(expression));
+        expr.push_str(&self.get_decision_code(decision, &cases));
+        expr.push_str("}()");
+        expr
+    }
+
+    // The decision tree for matching `__match`, of type `t` when it's known, against `cases`.
+    fn get_match_decision(&mut self, t: Option<Type>, cases: &[MatchCase]) -> Decision {
+        let mut rows: Vec<Row> = vec![];
+        for (i, case) in cases.iter().enumerate() {
+            for pattern in alternatives(&case.pattern) {
+                let row: Row = Row {
+                    tests: vec![("__match".to_string(), pattern, t.clone())],
+                    bindings: vec![],
+                    case: i,
+                };
+                rows.push(self.simplify(row));
+            }
+        }
+        self.get_decision(rows, cases)
+    }
+
+    // Picks the first test of the first row and splits the rows by its outcome.
+    fn get_decision(&mut self, rows: Vec<Row>, cases: &[MatchCase]) -> Decision {
+        let Some(first) = rows.first() else {
+            return Decision::Fail;
+        };
+        if first.tests.is_empty() {
+            let fallback: Option<Box<Decision>> = match cases[first.case].guard {
+                Some(_) => Some(Box::new(self.get_decision(rows[1..].to_vec(), cases))),
+                None => None,
+            };
+            return Decision::Leaf(first.bindings.clone(), first.case, fallback);
+        }
+        let (value, pattern, t): Test = first.tests[0].clone();
+        let default: Vec<Row> = rows.iter().filter(|row| !row.tests.iter().any(|(v, _, _)| *v == value)).cloned().collect();
+        match pattern {
+            Pattern::Constructor(name, _, _) => {
+                let (enum_name, _, _): (String, usize, Vec<Type>) = self.get_variant(&name, t.as_ref());
+                let mut variants: Vec<String> = vec![];
+                for row in rows.iter() {
+                    if let Some((_, Pattern::Constructor(variant, _, _), _)) = row.tests.iter().find(|(v, _, _)| *v == value) {
+                        if !variants.contains(variant) {
+                            variants.push(variant.clone());
+                        }
+                    }
+                }
+                let enum_type: Option<Type> = Some(Type::Unknown(enum_name.clone(), first.tests[0].1.span()));
+                // A generic enum's variants take the matched value's type arguments, where it has them.
+                let arguments: HashMap<String, Type> = match &t {
+                    Some(Type::GenericType(_, types, _)) => self.generics.get(&enum_name).into_iter().flatten().cloned().zip(types.iter().cloned()).collect(),
+                    _ => HashMap::new(),
+                };
+                let mut switch_cases: Vec<(usize, String, Decision)> = vec![];
+                for variant in variants.iter() {
+                    let (_, index, types): (String, usize, Vec<Type>) = self.get_variant(variant, enum_type.as_ref());
+                    let types: Vec<Type> = types.into_iter().map(|t| substitute(t, &arguments)).collect();
+                    let path: String = format!("std::get<{}>({})", self.get_variant_path(&enum_name, variant, &arguments), value);
+                    let specialized: Vec<Row> = rows.iter().filter_map(|row| {
+                        let position: Option<usize> = row.tests.iter().position(|(v, _, _)| *v == value);
+                        let Some(position) = position else {
+                            return Some(row.clone());
+                        };
+                        let Pattern::Constructor(name, args, _) = &row.tests[position].1 else {
+                            return None;
+                        };
+                        if name != variant {
+                            return None;
+                        }
+                        let mut row: Row = row.clone();
+                        row.tests.remove(position);
+                        let owner: String = format!("{}.{}", enum_name, variant);
+                        for (i, arg) in args.iter().enumerate() {
+                            let field: String = self.get_field(&owner, &path, &format!("__{}", i));
+                            row.tests.push((field, arg.clone(), types.get(i).cloned()));
+                        }
+                        Some(self.simplify(row))
+                    }).collect();
+                    switch_cases.push((index, variant.clone(), self.get_decision(specialized, cases)));
+                }
+                switch_cases.sort_by_key(|(index, _, _)| *index);
+                let all: usize = self.variants.get(&enum_name).map(|variants| variants.len()).unwrap_or(0);
+                let default: Decision = if variants.len() == all { Decision::Fail } else { self.get_decision(default, cases) };
+                Decision::Switch(value, switch_cases, Box::new(default))
+            }
+            Pattern::Literal(_, _) => {
+                let mut literals: Vec<String> = vec![];
+                for row in rows.iter() {
+                    let Some(position) = row.tests.iter().position(|(v, _, _)| *v == value) else {
+                        continue;
+                    };
+                    let Pattern::Literal(literal, _) = &row.tests[position].1 else {
+                        continue;
+                    };
+                    let literal: String = self.get_expression(*literal.clone());
+                    if !literals.contains(&literal) {
+                        literals.push(literal);
+                    }
+                }
+                let mut tests: Vec<(String, Decision)> = vec![];
+                for literal in literals {
+                    let mut specialized: Vec<Row> = vec![];
+                    for row in rows.iter() {
+                        let Some(position) = row.tests.iter().position(|(v, _, _)| *v == value) else {
+                            specialized.push(row.clone());
+                            continue;
+                        };
+                        let Pattern::Literal(other, _) = &row.tests[position].1 else {
+                            continue;
+                        };
+                        if self.get_expression(*other.clone()) == literal {
+                            let mut row: Row = row.clone();
+                            row.tests.remove(position);
+                            specialized.push(row);
+                        }
+                    }
+                    tests.push((literal, self.get_decision(specialized, cases)));
+                }
+                let default: Decision = self.get_decision(default, cases);
+                Decision::Literals(value, tests, Box::new(default))
+            }
+            _ => unreachable!("rows only test variants and literals"),
+        }
+    }
+
+    // Turns wildcards, bindings, tuple and data struct patterns, which always match, into
+    // bindings and tests of the parts.
+    fn simplify(&self, row: Row) -> Row {
+        let mut simplified: Row = Row { tests: vec![], bindings: row.bindings, case: row.case };
+        let mut tests: Vec<Test> = row.tests;
+        tests.reverse();
+        while let Some((value, pattern, t)) = tests.pop() {
+            match pattern {
+                Pattern::Wildcard(_) => {}
+                Pattern::Binding(name, _) => simplified.bindings.push((name, value)),
+                Pattern::As(name, pattern, _) => {
+                    simplified.bindings.push((name, value.clone()));
+                    tests.push((value, *pattern, t));
+                }
+                Pattern::Constructor(name, args, _) if self.structs.contains_key(&name) => {
+                    let fields: Vec<(String, Type)> = self.structs.get(&name).unwrap().clone();
+                    for ((field, field_type), arg) in fields.into_iter().zip(args).rev() {
+                        tests.push((self.get_field(&name, &value, &field), arg, Some(field_type)));
+                    }
+                }
+                Pattern::Struct(name, fields, _) => {
+                    let types: Vec<(String, Type)> = self.structs.get(&name).cloned().unwrap_or_default();
+                    for (field, pattern) in fields.into_iter().rev() {
+                        let field_type: Option<Type> = types.iter().find(|(n, _)| *n == field).map(|(_, t)| t.clone());
+                        tests.push((self.get_field(&name, &value, &field), pattern, field_type));
+                    }
+                }
+                Pattern::Tuple(patterns, _) => {
+                    let types: Vec<Type> = match t {
+                        Some(Type::Tuple(types, _)) => types,
+                        _ => vec![],
+                    };
+                    for (i, pattern) in patterns.into_iter().enumerate().rev() {
+                        tests.push((format!("std::get<{}>({})", i, value), pattern, types.get(i).cloned()));
+                    }
+                }
+                Pattern::Or(_, _) => unreachable!("alternatives are split into separate rows"),
+                pattern => simplified.tests.push((value, pattern, t)),
+            }
+        }
+        simplified
+    }
+
+    fn get_decision_code(&mut self, decision: Decision, cases: &[MatchCase]) -> String {
+        match decision {
+            Decision::Fail => "std::abort();\n".to_string(),
+            Decision::Leaf(bindings, case, fallback) => {
+                let scope: usize = self.locals.len();
+                // Bindings get their own scope, as another case may bind the same names.
+                let scoped: bool = !bindings.is_empty();
+                let mut code: String = String::from(if scoped { "{\n" } else { "" });
+                for (name, value) in bindings {
+                    code.push_str(&format!("const auto& {} = {};\n", name, value));
+                    self.locals.push(name);
+                }
+                let body: String = self.get_case_body(cases[case].body.clone());
+                match cases[case].guard.clone() {
+                    Some(guard) => code.push_str(&format!("if ({}) {{\n{}}}\n", self.get_expression(*guard), body)),
+                    None => code.push_str(&body),
+                }
+                if scoped {
+                    code.push_str("}\n");
+                }
+                self.locals.truncate(scope);
+                if let Some(fallback) = fallback {
+                    code.push_str(&self.get_decision_code(*fallback, cases));
+                }
+                code
+            }
+            Decision::Switch(value, switch_cases, default) => {
+                let mut code: String = format!("switch ({}.index()) {{\n", value);
+                for (index, variant, decision) in switch_cases {
+                    code.push_str(&format!("case {}: {{ // {}\n", index, variant));
+                    code.push_str(&self.get_decision_code(decision, cases));
+                    code.push_str("}\n");
+                }
+                code.push_str("default: {\n");
+                code.push_str(&self.get_decision_code(*default, cases));
+                code.push_str("}\n}\n");
+                code
+            }
+            Decision::Literals(value, tests, default) => {
+                let mut code: String = String::new();
+                for (literal, decision) in tests {
+                    code.push_str(&format!("if ({} == {}) {{\n", value, literal));
+                    code.push_str(&self.get_decision_code(decision, cases));
+                    code.push_str("} else ");
+                }
+                code.push_str("{\n");
+                code.push_str(&self.get_decision_code(*default, cases));
+                code.push_str("}\n");
+                code
+            }
+        }
+    }
+    // What each name in a `let` pattern refers to, the type checker has made sure it can't fail.
+    pub(crate) fn get_pattern_bindings(&self, pattern: Pattern, value: String) -> Vec<(String, String)> {
+        let row: Row = Row { tests: vec![(value, pattern, None)], bindings: vec![], case: 0 };
+        self.simplify(row).bindings
+    }
+    fn get_case_body(&mut self, body: Vec<Statement>) -> String {
+        let mut code: String = String::new();
+        let last: usize = body.len().saturating_sub(1);
+        for (i, statement) in body.into_iter().enumerate() {
+            match statement {
+                // `return` works for calls returning `void` too, so every case can use it.
+                Statement::Expression(expression, _) if i == last => {
+                    code.push_str(&format!("return {};\n", self.get_expression(expression)));
+                }
+                statement => code.push_str(&self.get_statement(statement)),
+            }
+        }
+        code
+    }
+
+    // The data enum `variant` belongs to, its index in it and the types of its fields. The type
+    // of the matched value decides when it is known, otherwise the variant's name has to be unique.
+    fn get_variant(&self, variant: &str, t: Option<&Type>) -> (String, usize, Vec<Type>) {
+        let find = |variants: &Vec<EnumVarient>| variants.iter().enumerate().find_map(|(i, v)| match v {
+            EnumVarient::Tuple(name, types, _) if name == variant => Some((i, types.clone())),
+            EnumVarient::Unit(name, _) if name == variant => Some((i, vec![])),
+            _ => None,
+        });
+        if let Some(enum_name) = t.and_then(type_base_name) {
+            if let Some((index, types)) = self.variants.get(&enum_name).and_then(find) {
+                return (enum_name, index, types);
+            }
+        }
+        let candidates: Vec<(String, usize, Vec<Type>)> = self.variants.iter()
+            .filter_map(|(enum_name, variants)| find(variants).map(|(index, types)| (enum_name.clone(), index, types)))
+            .collect();
+        // The type checker rejects patterns naming no variant or an ambiguous one.
+        match candidates.len() {
+            1 => candidates[0].clone(),
+            0 => unreachable!("`{}` is not a variant of any data enum", variant),
+            _ => unreachable!("`{}` is a variant of several data enums", variant),
+        }
+    }
+}
+
+// Splits `A(x) | B(x)` and alternatives nested inside constructors into patterns without
+// any, so each can bind its names on its own.
+fn alternatives(pattern: &Pattern) -> Vec<Pattern> {
+    match pattern {
+        Pattern::Or(patterns, _) => patterns.iter().flat_map(alternatives).collect(),
+        Pattern::As(name, pattern, span) => {
+            alternatives(pattern).into_iter().map(|p| Pattern::As(name.clone(), Box::new(p), span.clone())).collect()
+        }
+        Pattern::Constructor(name, args, span) => {
+            product(args.iter().map(alternatives).collect()).into_iter()
+                .map(|args| Pattern::Constructor(name.clone(), args, span.clone()))
+                .collect()
+        }
+        Pattern::Struct(name, fields, span) => {
+            product(fields.iter().map(|(_, pattern)| alternatives(pattern)).collect()).into_iter()
+                .map(|patterns| {
+                    let fields: Vec<(String, Pattern)> = fields.iter().map(|(field, _)| field.clone()).zip(patterns).collect();
+                    Pattern::Struct(name.clone(), fields, span.clone())
+                })
+                .collect()
+        }
+        Pattern::Tuple(patterns, span) => {
+            product(patterns.iter().map(alternatives).collect()).into_iter()
+                .map(|patterns| Pattern::Tuple(patterns, span.clone()))
+                .collect()
+        }
+        pattern => vec![pattern.clone()],
+    }
+}
+// Every way of picking one pattern from each of `choices`.
+fn product(choices: Vec<Vec<Pattern>>) -> Vec<Vec<Pattern>> {
+    let mut combinations: Vec<Vec<Pattern>> = vec![vec![]];
+    for choice in choices {
+        combinations = combinations.iter()
+            .flat_map(|combination| choice.iter().map(move |pattern| {
+                let mut combination: Vec<Pattern> = combination.clone();
+                combination.push(pattern.clone());
+                combination
+            }))
+            .collect();
+    }
+    combinations
+}
+// `t` with the type parameters in `arguments` replaced by their types.
+fn substitute(t: Type, arguments: &HashMap<String, Type>) -> Type {
+    match t {
+        Type::Generic(name, span) | Type::GenericParameter(name, span) => match arguments.get(&name) {
+            Some(t) => t.clone(),
+            None => Type::Generic(name, span),
+        },
+        Type::GenericType(base, types, span) => {
+            Type::GenericType(Box::new(substitute(*base, arguments)), types.into_iter().map(|t| substitute(t, arguments)).collect(), span)
+        }
+        Type::Optional(inner, span) => Type::Optional(Box::new(substitute(*inner, arguments)), span),
+        Type::Array(inner, span) => Type::Array(Box::new(substitute(*inner, arguments)), span),
+        Type::Function(types, result, span) => {
+            Type::Function(types.into_iter().map(|t| substitute(t, arguments)).collect(), Box::new(substitute(*result, arguments)), span)
+        }
+        Type::Tuple(types, span) => Type::Tuple(types.into_iter().map(|t| substitute(t, arguments)).collect(), span),
+        t => t,
+    }
+}
+
+// The name of the data type `t` refers to, without any type arguments.
+fn type_base_name(t: &Type) -> Option<String> {
+    match t {
+        Type::DataEnum(name, _) | Type::DataStruct(name, _) | Type::Alias(name, _) |
+        Type::Object(name, _) | Type::Unknown(name, _) | Type::Generic(name, _) => Some(name.clone()),
+        Type::GenericType(base, _, _) => type_base_name(base),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lexer::{layout::Layout, lexer::Lexer, span::FileId};
+    use parser::parser::Parser;
+    use super::*;
+
+    // The code generator for `source`, whose last statement is a procedure matching on its
+    // argument, and the cases of that match.
+    fn compile(source: &str) -> (Codegen, Vec<MatchCase>) {
+        let mut parser: Parser = Parser::new(Layout::new(Lexer::new(source, FileId(0))).collect());
+        let (statements, errors) = parser.parse();
+        assert!(errors.is_empty(), "{:?}", errors);
+        let Some(Statement::Procedure(_, _, _, _, Expression::Match(_, cases, else_case, _), _)) = statements.last().cloned() else {
+            panic!("expected a procedure matching on its argument");
+        };
+        let cases: Vec<MatchCase> = cases.into_iter().chain(else_case).collect();
+        (Codegen::new("M.real".to_string(), statements), cases)
+    }
+
+    #[test]
+    fn failed_guard_falls_through_to_the_next_case() {
+        let (mut codegen, cases) = compile("module M
+data Shape =
+    | Circle(Int)
+    | Empty
+procedure F(s: Shape, b: Bool) -> Int = match s with
+    | Circle(r) if b -> r
+    | Circle(_) -> 1
+    | _ -> 2
+");
+        let decision: Decision = codegen.get_match_decision(None, &cases);
+        let Decision::Switch(value, switch_cases, default) = decision else {
+            panic!("expected a switch, found {:?}", decision);
+        };
+        assert_eq!(value, "__match");
+        assert!(matches!(*default, Decision::Leaf(_, 2, None)), "{:?}", default);
+        let [(0, variant, Decision::Leaf(bindings, 0, Some(fallback)))] = switch_cases.as_slice() else {
+            panic!("expected only `Circle` to be tested, found {:?}", switch_cases);
+        };
+        assert_eq!(variant, "Circle");
+        assert_eq!(bindings, &vec![("r".to_string(), "std::get<Shape_Variants::Circle>(__match).__0".to_string())]);
+        assert!(matches!(fallback.as_ref(), Decision::Leaf(_, 1, None)), "{:?}", fallback);
+    }
+    #[test]
+    fn guarded_wildcard_falls_through_to_the_tests_after_it() {
+        let (mut codegen, cases) = compile("module M
+data Shape =
+    | Circle(Int)
+    | Square(Int)
+    | Empty
+procedure F(s: Shape, b: Bool) -> Int = match s with
+    | x if b -> 0
+    | Circle(_) | Square(_) -> 1
+    | Empty -> 2
+");
+        let decision: Decision = codegen.get_match_decision(None, &cases);
+        let Decision::Leaf(bindings, 0, Some(fallback)) = decision else {
+            panic!("expected the guarded case first, found {:?}", decision);
+        };
+        assert_eq!(bindings, vec![("x".to_string(), "__match".to_string())]);
+        let Decision::Switch(_, switch_cases, default) = *fallback else {
+            panic!("expected a switch after the guard, found {:?}", fallback);
+        };
+        let cases: Vec<(usize, usize)> = switch_cases.iter().map(|(index, _, decision)| match decision {
+            Decision::Leaf(_, case, None) => (*index, *case),
+            decision => panic!("expected a case, found {:?}", decision),
+        }).collect();
+        assert_eq!(cases, vec![(0, 1), (1, 1), (2, 2)]);
+        assert!(matches!(*default, Decision::Fail), "{:?}", default);
+    }
+    #[test]
+    fn guard_fallthrough_is_emitted_after_the_guarded_body() {
+        let (mut codegen, cases) = compile("module M
+data Shape =
+    | Circle(Int)
+    | Empty
+procedure F(s: Shape, b: Bool) -> Int = match s with
+    | Empty if b -> 0
+    | _ -> 1
+");
+        let decision: Decision = codegen.get_match_decision(None, &cases);
+        let code: String = codegen.get_decision_code(decision, &cases);
+        assert_eq!(code, "switch (__match.index()) {\ncase 1: { // Empty\nif (b) {\nreturn 0;\n}\nreturn 1;\n}\ndefault: {\nreturn 1;\n}\n}\n");
+    }
+    #[test]
+    fn shared_variant_name_is_resolved_by_the_matched_type() {
+        let (mut codegen, cases) = compile("module M
+data OptA =
+    | None
+    | SomeA(Int)
+data OptB =
+    | SomeB(Bool)
+    | None
+procedure F(o: OptB) -> Int = match o with
+    | None -> 0
+    | SomeB(_) -> 1
+");
+        let t: Type = Type::DataEnum("OptB".to_string(), cases[0].pattern.span());
+        let decision: Decision = codegen.get_match_decision(Some(t), &cases);
+        let Decision::Switch(_, switch_cases, _) = decision else {
+            panic!("expected a switch, found {:?}", decision);
+        };
+        let variants: Vec<(usize, String)> = switch_cases.iter().map(|(index, variant, _)| (*index, variant.clone())).collect();
+        assert_eq!(variants, vec![(0, "SomeB".to_string()), (1, "None".to_string())]);
+    }
+    #[test]
+    fn known_result_type_is_the_lambdas_return_type() {
+        let mut parser: Parser = Parser::new(Layout::new(Lexer::new("module M
+procedure F(b: Bool) -> Int = match b with
+    | true -> 0
+    | false -> 1
+", FileId(0))).collect());
+        let (statements, errors) = parser.parse();
+        assert!(errors.is_empty(), "{:?}", errors);
+        let Some(Statement::Procedure(_, _, _, _, Expression::Match(value, cases, else_case, span), _)) = statements.last().cloned() else {
+            panic!("expected a procedure matching on its argument");
+        };
+        let mut codegen: Codegen = Codegen::new("M.real".to_string(), statements);
+        codegen.result_types.insert(span.clone(), Type::Int(span.clone()));
+        let code: String = codegen.get_match(*value, cases, else_case, span);
+        assert!(code.starts_with("[&]() -> int {\n"), "{}", code);
+    }
+}
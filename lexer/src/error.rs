@@ -0,0 +1,21 @@
+use crate::span::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    UnexpectedCharacter,        // a character that can't start any token
+    ExpectedCharacter,          // a token that was cut short, like `r#` without a quote
+    UnterminatedString,         // a string literal that runs into the end of the file
+    InconsistentIndentation,    // mixed tabs and spaces, or an unindent to an unknown level
+    LiteralOutOfRange,          // a numeric literal too large for its type
+    InvalidLiteral,             // a numeric literal with bad digits or an unknown suffix
+    InvalidEscape,              // an unknown or malformed escape sequence in a string or char
+    InvalidCharacterLiteral,    // an empty, unterminated, multi-character or non-ASCII char literal
+    UnterminatedComment,        // a block comment that runs into the end of the file
+}
+
+#[derive(Debug, Clone)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub message: String,
+    pub span: Span,
+}
@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+use crate::{
+    lexer::Lexer,
+    span::{
+        FileId,
+        Span,
+    },
+    tokens::{
+        TokenKind,
+        Token,
+    },
+    error::{
+        LexErrorKind,
+        LexError,
+    },
+};
+
+// Haskell-style layout pass over the raw token stream. Every logical line ends in a single
+// `Newline` (blank and comment-only lines are dropped), and a change in indentation at the
+// start of a line produces `Indent` or one or more `Dedent`s before its first token. Inside
+// parentheses, brackets, braces and interpolated strings the layout is ignored, so expressions can span lines freely.
+// Doc comments only count when they start a line; anywhere else they are plain comments.
+#[derive(Debug, Clone)]
+pub struct Layout<'a> {
+    lexer: Lexer<'a>,
+    source: &'a str,
+    file: FileId,
+    indents: Vec<usize>,
+    indent_char: Option<char>,
+    depth: usize,
+    at_line_start: bool,
+    last: Option<TokenKind>,
+    pending: VecDeque<Token>,
+    finished: bool,
+    errors: Vec<LexError>,
+}
+
+impl<'a> Layout<'a> {
+    pub fn new(lexer: Lexer<'a>) -> Self {
+        let source: &'a str = lexer.source();
+        let file: FileId = lexer.file();
+        Self {
+            lexer,
+            source,
+            file,
+            indents: vec![0],
+            indent_char: None,
+            depth: 0,
+            at_line_start: true,
+            last: None,
+            pending: VecDeque::new(),
+            finished: false,
+            errors: Vec::new(),
+        }
+    }
+    // Diagnostics from both the lexer and the layout pass, in source order.
+    pub fn errors(&self) -> Vec<LexError> {
+        let mut errors: Vec<LexError> = self.lexer.errors().to_vec();
+        errors.extend(self.errors.iter().cloned());
+        errors.sort_by_key(|error| error.span.start);
+        errors
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        loop {
+            if let Some(token) = self.pending.pop_front() {
+                self.last = Some(token.kind.clone());
+                return Some(token);
+            }
+            if self.finished {
+                return None;
+            }
+            let token: Token = match self.lexer.next() {
+                Some(token) => token,
+                None => {
+                    self.finish();
+                    continue;
+                }
+            };
+            match token.kind {
+                TokenKind::Newline => {
+                    if self.depth > 0 || self.last.is_none() || self.last == Some(TokenKind::Newline) {
+                        continue;
+                    }
+                    self.at_line_start = true;
+                    self.pending.push_back(token);
+                }
+                TokenKind::DocComment if !self.at_line_start || self.depth > 0 => continue,
+                _ => {
+                    if self.at_line_start && self.depth == 0 {
+                        self.indent(&token);
+                    }
+                    self.at_line_start = false;
+                    match token.kind {
+                        TokenKind::OpenParenthesis | TokenKind::OpenBracket | TokenKind::OpenBrace | TokenKind::StringStart => self.depth += 1,
+                        TokenKind::CloseParenthesis | TokenKind::CloseBracket | TokenKind::CloseBrace | TokenKind::StringEnd => self.depth = self.depth.saturating_sub(1),
+                        _ => {}
+                    }
+                    self.pending.push_back(token);
+                }
+            }
+        }
+    }
+    // Compares the indentation of the line `token` starts with the enclosing blocks.
+    fn indent(&mut self, token: &Token) {
+        let line_start: usize = self.source[..token.span.start].rfind('\n').map_or(0, |i| i + 1);
+        let whitespace: &str = &self.source[line_start..token.span.start];
+        let whitespace: &str = &whitespace[..whitespace.len() - whitespace.trim_start_matches([' ', '\t']).len()];
+        let span: Span = Span::new(self.file, token.span.start, token.span.start);
+        for c in whitespace.chars() {
+            match self.indent_char {
+                None => self.indent_char = Some(c),
+                Some(indent_char) if indent_char != c => {
+                    self.error(LexErrorKind::InconsistentIndentation, "indentation mixes tabs and spaces".to_string(), Span::new(self.file, line_start, token.span.start));
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let width: usize = whitespace.chars().count();
+        let current: usize = *self.indents.last().unwrap();
+        if width > current {
+            self.indents.push(width);
+            self.pending.push_back(Token { kind: TokenKind::Indent, literal: None, span });
+        } else if width < current {
+            while width < *self.indents.last().unwrap() {
+                self.indents.pop();
+                self.pending.push_back(Token { kind: TokenKind::Dedent, literal: None, span: span.clone() });
+            }
+            if width != *self.indents.last().unwrap() {
+                self.error(LexErrorKind::InconsistentIndentation, "unindent does not match any outer indentation level".to_string(), span);
+                self.indents.push(width);
+            }
+        }
+    }
+    // Closes the last line and every open block at the end of the file.
+    fn finish(&mut self) {
+        let span: Span = Span::new(self.file, self.source.len(), self.source.len());
+        if self.last.is_some() && self.last != Some(TokenKind::Newline) {
+            self.pending.push_back(Token { kind: TokenKind::Newline, literal: None, span: span.clone() });
+        }
+        while self.indents.len() > 1 {
+            self.indents.pop();
+            self.pending.push_back(Token { kind: TokenKind::Dedent, literal: None, span: span.clone() });
+        }
+        self.finished = true;
+    }
+    fn error(&mut self, kind: LexErrorKind, message: String, span: Span) {
+        self.errors.push(LexError { kind, message, span });
+    }
+}
+
+impl Iterator for Layout<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        self.next_token()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        Layout::new(Lexer::new(source, FileId(0))).map(|token| token.kind).collect()
+    }
+    fn errors(source: &str) -> Vec<LexErrorKind> {
+        let mut layout: Layout = Layout::new(Lexer::new(source, FileId(0)));
+        layout.by_ref().for_each(drop);
+        layout.errors().into_iter().map(|error| error.kind).collect()
+    }
+
+    #[test]
+    fn indented_lines_open_and_close_blocks() {
+        assert_eq!(kinds("a\n    b\nc\n"), vec![
+            TokenKind::Identifier, TokenKind::Newline,
+            TokenKind::Indent, TokenKind::Identifier, TokenKind::Newline,
+            TokenKind::Dedent, TokenKind::Identifier, TokenKind::Newline,
+        ]);
+    }
+    #[test]
+    fn unindenting_several_levels_closes_each_block() {
+        assert_eq!(kinds("a\n  b\n    c\nd"), vec![
+            TokenKind::Identifier, TokenKind::Newline,
+            TokenKind::Indent, TokenKind::Identifier, TokenKind::Newline,
+            TokenKind::Indent, TokenKind::Identifier, TokenKind::Newline,
+            TokenKind::Dedent, TokenKind::Dedent, TokenKind::Identifier, TokenKind::Newline,
+        ]);
+    }
+    #[test]
+    fn end_of_file_closes_open_blocks() {
+        assert_eq!(kinds("a\n  b\n    c"), vec![
+            TokenKind::Identifier, TokenKind::Newline,
+            TokenKind::Indent, TokenKind::Identifier, TokenKind::Newline,
+            TokenKind::Indent, TokenKind::Identifier, TokenKind::Newline,
+            TokenKind::Dedent, TokenKind::Dedent,
+        ]);
+    }
+    #[test]
+    fn blank_and_comment_lines_are_ignored() {
+        assert_eq!(kinds("a\n\n    // comment\n  b\n"), vec![
+            TokenKind::Identifier, TokenKind::Newline,
+            TokenKind::Indent, TokenKind::Identifier, TokenKind::Newline,
+            TokenKind::Dedent,
+        ]);
+    }
+    #[test]
+    fn brackets_ignore_layout() {
+        assert_eq!(kinds("f(a,\n    b)\n"), vec![
+            TokenKind::Identifier, TokenKind::OpenParenthesis, TokenKind::Identifier, TokenKind::Comma,
+            TokenKind::Identifier, TokenKind::CloseParenthesis, TokenKind::Newline,
+        ]);
+    }
+    #[test]
+    fn unindent_to_unknown_level_is_an_error() {
+        assert_eq!(errors("a\n    b\n  c\n"), vec![LexErrorKind::InconsistentIndentation]);
+    }
+    #[test]
+    fn mixing_tabs_and_spaces_is_an_error() {
+        assert_eq!(errors("a\n  b\nc\n\td\n"), vec![LexErrorKind::InconsistentIndentation]);
+    }
+}
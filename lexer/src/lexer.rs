@@ -1,64 +1,121 @@
+use std::{
+    iter::Peekable,
+    str::CharIndices,
+};
 use crate::{
-    span::Span,
+    span::{
+        FileId,
+        Span,
+    },
     tokens::{
         TokenKind,
         Token,
     },
+    error::{
+        LexErrorKind,
+        LexError,
+    },
+    literal,
 };
 
+// The lexer walks the source once through a peekable `char_indices` cursor, so every
+// span is a byte range into the source. It yields tokens lazily through `Iterator`,
+// collecting any diagnostics on the side; `lex` is a convenience wrapper around that.
 #[derive(Debug, Clone)]
-pub struct Lexer {
-    source: String,
-    tokens: Vec<Token>,
-    current: usize,
-    start: usize,
-    end: usize,
+pub struct Lexer<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    file: FileId,
+    errors: Vec<LexError>,
+    // One entry per string whose `${...}` we are inside of: where the string started, and
+    // how many of the expression's own braces are open.
+    interpolations: Vec<(usize, usize)>,
 }
 
-impl Lexer {
-    pub fn new(source: String) -> Self {
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str, file: FileId) -> Self {
         Self {
             source,
-            tokens: Vec::new(),
-            current: 0,
-            start: 0,
-            end: 0,
+            chars: source.char_indices().peekable(),
+            file,
+            errors: Vec::new(),
+            interpolations: Vec::new(),
+        }
+    }
+    pub fn lex(&mut self) -> Result<Vec<Token>, Vec<LexError>> {
+        let tokens: Vec<Token> = self.by_ref().collect();
+        if self.errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(std::mem::take(&mut self.errors))
         }
     }
-    pub fn lex(&mut self) -> Vec<Token> {
-        while self.current < self.source.len() {
-            match self.current().clone() {
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+    pub fn file(&self) -> FileId {
+        self.file
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        while let Some(c) = self.current() {
+            let start: usize = self.offset();
+            match c {
                 ' ' | '\r' | '\t' => {
                     self.advance();
-                    self.start += 1;
-                    self.end = self.start;
                 }
                 '\n' => {
-                    let start: usize = self.start;
                     self.advance();
-                    self.start += 1;
-                    self.end = start;
-                    self.tokens.push(Token { kind: TokenKind::Newline, literal: None, span: Span { start, end: self.end }})
+                    return Some(self.token(TokenKind::Newline, None, start));
+                }
+                'r' if matches!(self.peek(1), Some('"' | '#')) => {
+                    self.advance();
+                    let mut hashes: usize = 0;
+                    while self.current() == Some('#') {
+                        self.advance();
+                        hashes += 1;
+                    }
+                    if self.current() != Some('"') {
+                        self.error(LexErrorKind::ExpectedCharacter, "expected '\"' to start a raw string".to_string(), start);
+                        continue;
+                    }
+                    self.advance();
+                    let terminator: String = format!("\"{}", "#".repeat(hashes));
+                    if let Some(token) = self.raw_string(start, &terminator) {
+                        return Some(token);
+                    }
+                }
+                '"' if self.peek(1) == Some('"') && self.peek(2) == Some('"') => {
+                    for _ in 0..3 {
+                        self.advance();
+                    }
+                    if let Some(token) = self.raw_string(start, "\"\"\"") {
+                        return Some(token);
+                    }
                 }
                 'a'..='z' | 'A'..='Z' | '_' => {
-                    let mut literal: String = String::new();
-                    let start: usize = self.start;
-                    while self.current().is_alphanumeric() || self.current() == '_' {
-                        literal.push(self.current());
-                        self.start += 1;
+                    while self.current().is_some_and(|c| c.is_alphanumeric() || c == '_') {
                         self.advance();
                     }
-                    self.end = self.start;
-                    let kind: TokenKind = match literal.clone().as_str() {
+                    let literal: &str = &self.source[start..self.offset()];
+                    let kind: TokenKind = match literal {
                         "Unit" => TokenKind::Unit,
                         "Int" => TokenKind::Int,
+                        "Float" => TokenKind::Float,
                         "Char" => TokenKind::Char,
                         "Bool" => TokenKind::Bool,
+                        "true" => TokenKind::True,
+                        "false" => TokenKind::False,
                         "data" => TokenKind::Data,
                         "alias" => TokenKind::Alias,
                         "object" => TokenKind::Object,
                         "const" => TokenKind::Const,
                         "procedure" => TokenKind::Procedure,
+                        "let" => TokenKind::Let,
+                        "in" => TokenKind::In,
                         "trait" => TokenKind::Trait,
                         "of" => TokenKind::Of,
                         "module" => TokenKind::Module,
@@ -71,224 +128,373 @@ impl Lexer {
                         "virtual" => TokenKind::Virtual,
                         "override" => TokenKind::Override,
                         "unsafe" => TokenKind::Unsafe,
+                        "infixl" => TokenKind::Infixl,
+                        "infixr" => TokenKind::Infixr,
+                        "infix" => TokenKind::Infix,
                         "match" => TokenKind::Match,
                         "with" => TokenKind::With,
                         "else" => TokenKind::Else,
+                        "if" => TokenKind::If,
+                        "then" => TokenKind::Then,
                         "cpp" => TokenKind::Cpp,
                         _ => TokenKind::Identifier,
                     };
-                    self.tokens.push(Token { kind, literal: Some(literal), span: Span { start, end: self.end }})
+                    return Some(self.token(kind, Some(literal.to_string()), start));
                 }
                 '0'..='9' => {
-                    let mut literal: String = String::new();
-                    let start: usize = self.start;
-                    while self.current().is_numeric() {
-                        literal.push(self.current());
-                        self.start += 1;
+                    let mut kind: TokenKind = TokenKind::IntegerLiteral;
+                    if c == '0' && matches!(self.peek(1), Some('x' | 'X' | 'b' | 'B' | 'o' | 'O')) {
                         self.advance();
-                    }
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::IntegerLiteral, literal: Some(literal), span: Span { start, end: self.end }})
-                }
-                '"' => {
-                    let mut literal: String = String::new();
-                    self.advance();
-                    self.start += 1;
-                    let start: usize = self.start;
-                    while self.current() != '"' {
-                        literal.push(self.current());
-                        if self.current() == '\\' {
-                            self.start += 1;
+                        self.advance();
+                    } else {
+                        self.digits();
+                        // A '.' only starts a fraction when a digit follows, so `1..2` and `x.0.y` still lex.
+                        if self.current() == Some('.') && self.peek(1).is_some_and(|c| c.is_ascii_digit()) {
+                            kind = TokenKind::FloatLiteral;
                             self.advance();
-                            literal.push(self.current());
+                            self.digits();
                         }
-                        self.start += 1;
+                        if matches!(self.current(), Some('e') | Some('E')) {
+                            let sign: usize = if matches!(self.peek(1), Some('+') | Some('-')) { 1 } else { 0 };
+                            if self.peek(1 + sign).is_some_and(|c| c.is_ascii_digit()) {
+                                kind = TokenKind::FloatLiteral;
+                                for _ in 0..(1 + sign) {
+                                    self.advance();
+                                }
+                                self.digits();
+                            }
+                        }
+                    }
+                    // Everything alphanumeric that follows belongs to the literal, so hex digits,
+                    // suffixes and stray letters are all checked (and reported) as one piece.
+                    while self.current().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
                         self.advance();
                     }
-                    self.end = self.start;
-                    self.advance();
-                    self.start += 1;
-                    self.tokens.push(Token { kind: TokenKind::StringLiteral, literal: Some(literal), span: Span { start, end: self.end }})
-                }
-                '(' => {
-                    let start: usize = self.start;
-                    self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::OpenParenthesis, literal: None, span: Span { start, end: self.end }})
+                    let literal: String = self.source[start..self.offset()].to_string();
+                    let checked: Result<(), (LexErrorKind, String)> = if kind == TokenKind::FloatLiteral {
+                        literal::parse_float(&literal).map(|_| ())
+                    } else {
+                        literal::parse_integer(&literal).map(|_| ())
+                    };
+                    if let Err((error, message)) = checked {
+                        self.error(error, message, start);
+                        continue;
+                    }
+                    return Some(self.token(kind, Some(literal), start));
                 }
-                ')' => {
-                    let start: usize = self.start;
+                '"' => {
                     self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::CloseParenthesis, literal: None, span: Span { start, end: self.end }})
+                    if let Some(token) = self.string(start, false) {
+                        return Some(token);
+                    }
                 }
-                '[' => {
-                    let start: usize = self.start;
+                '\'' => {
                     self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::OpenBracket, literal: None, span: Span { start, end: self.end }})
-                }
-                ']' => {
-                    let start: usize = self.start;
+                    let value: Option<char> = match self.current() {
+                        Some('\\') => {
+                            let escape_start: usize = self.offset();
+                            self.advance();
+                            self.escape(escape_start)
+                        }
+                        Some('\'') => {
+                            self.advance();
+                            self.error(LexErrorKind::InvalidCharacterLiteral, "empty character literal".to_string(), start);
+                            continue;
+                        }
+                        Some('\n') | None => None,
+                        Some(c) => {
+                            self.advance();
+                            Some(c)
+                        }
+                    };
+                    if self.current() != Some('\'') {
+                        while self.current().is_some_and(|c| c != '\'' && c != '\n') {
+                            self.advance();
+                        }
+                        if self.current() == Some('\'') {
+                            self.advance();
+                            self.error(LexErrorKind::InvalidCharacterLiteral, "character literal may only contain one character".to_string(), start);
+                        } else {
+                            self.error(LexErrorKind::InvalidCharacterLiteral, "unterminated character literal".to_string(), start);
+                        }
+                        continue;
+                    }
                     self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::CloseBracket, literal: None, span: Span { start, end: self.end }})
+                    // A `Char` is a C++ `char`, which only holds ASCII.
+                    if value.is_some_and(|c| !c.is_ascii()) {
+                        self.error(LexErrorKind::InvalidCharacterLiteral, "character literal must be ASCII".to_string(), start);
+                        continue;
+                    }
+                    if let Some(value) = value {
+                        return Some(self.token(TokenKind::CharLiteral, Some(value.to_string()), start));
+                    }
                 }
+                '(' => return Some(self.single(TokenKind::OpenParenthesis, start)),
+                ')' => return Some(self.single(TokenKind::CloseParenthesis, start)),
+                '[' => return Some(self.single(TokenKind::OpenBracket, start)),
+                ']' => return Some(self.single(TokenKind::CloseBracket, start)),
                 '{' => {
-                    let start: usize = self.start;
-                    self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::OpenBrace, literal: None, span: Span { start, end: self.end }})
+                    if let Some((_, braces)) = self.interpolations.last_mut() {
+                        *braces += 1;
+                    }
+                    return Some(self.single(TokenKind::OpenBrace, start));
                 }
                 '}' => {
-                    let start: usize = self.start;
-                    self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::CloseBrace, literal: None, span: Span { start, end: self.end }})
-                }
-                ':' => {
-                    let start: usize = self.start;
-                    self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::Colon, literal: None, span: Span { start, end: self.end }})
-                }
-                ';' => {
-                    let start: usize = self.start;
-                    self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::Semicolon, literal: None, span: Span { start, end: self.end }})
-                }
-                '.' => {
-                    let start: usize = self.start;
-                    self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::Dot, literal: None, span: Span { start, end: self.end }})
-                }
-                ',' => {
-                    let start: usize = self.start;
-                    self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::Comma, literal: None, span: Span { start, end: self.end }})
-                }
-                '|' => {
-                    let start: usize = self.start;
-                    self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::Pipe, literal: None, span: Span { start, end: self.end }})
-                }
-                '?' => {
-                    let start: usize = self.start;
-                    self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::QuestionMark, literal: None, span: Span { start, end: self.end }})
+                    match self.interpolations.last_mut() {
+                        Some((_, 0)) => {
+                            self.advance();
+                            if let Some(token) = self.string(start, true) {
+                                return Some(token);
+                            }
+                            continue;
+                        }
+                        Some((_, braces)) => *braces -= 1,
+                        None => {}
+                    }
+                    return Some(self.single(TokenKind::CloseBrace, start));
                 }
-                '=' => {
-                    let start: usize = self.start;
+                ':' => return Some(self.single(TokenKind::Colon, start)),
+                ';' => return Some(self.single(TokenKind::Semicolon, start)),
+                '.' if self.peek(1) == Some('.') => {
                     self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::Equal, literal: None, span: Span { start, end: self.end }})
+                    return Some(self.single(TokenKind::DotDot, start));
                 }
-                '<' => {
-                    let start: usize = self.start;
+                '.' => return Some(self.single(TokenKind::Dot, start)),
+                ',' => return Some(self.single(TokenKind::Comma, start)),
+                '?' => return Some(self.single(TokenKind::QuestionMark, start)),
+                '\\' => return Some(self.single(TokenKind::Backslash, start)),
+                '/' if matches!(self.peek(1), Some('/' | '*')) => {
                     self.advance();
-                    self.start += 1;
-                    if self.current() == ':' {
-                        self.advance();
-                        self.start += 1;
-                        self.end = self.start;
-                        self.tokens.push(Token { kind: TokenKind::LessColon, literal: None, span: Span { start, end: self.end }})
+                    if self.current() == Some('/') {
+                        // `///` starts a doc comment, but `////...` is just a line comment.
+                        let doc: bool = self.peek(1) == Some('/') && self.peek(2) != Some('/');
+                        while self.current().is_some_and(|c| c != '\n') {
+                            self.advance();
+                        }
+                        if doc {
+                            let text: &str = self.source[start + 3..self.offset()].trim_end();
+                            let text: String = text.strip_prefix(' ').unwrap_or(text).to_string();
+                            return Some(self.token(TokenKind::DocComment, Some(text), start));
+                        }
                     } else {
-                        panic!("Unexpected token '{}'", self.current())
-                    }
-                }
-                '>' => {
-                    let start: usize = self.start;
-                    self.advance();
-                    self.start += 1;
-                    if self.current() == ':' {
                         self.advance();
-                        self.start += 1;
-                        self.end = self.start;
-                        self.tokens.push(Token { kind: TokenKind::GreaterColon, literal: None, span: Span { start, end: self.end }})
-                    } else {
-                        panic!("Unexpected token '{}'", self.current())
+                        self.block_comment(start);
                     }
                 }
-                '+' => {
-                    let start: usize = self.start;
+                c if is_operator_character(c) => return Some(self.operator(start)),
+                c => {
                     self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::Plus, literal: None, span: Span { start, end: self.end }})
+                    self.error(LexErrorKind::UnexpectedCharacter, format!("unexpected character '{}'", c), start);
                 }
-                '-' => {
-                    let start: usize = self.start;
+            }
+        }
+        while let Some((start, _)) = self.interpolations.pop() {
+            self.error(LexErrorKind::UnterminatedString, "unterminated string interpolation".to_string(), start);
+        }
+        None
+    }
+
+    // Lexes the text of a string up to its closing quote or the next `${`. `continued` is true
+    // when resuming after the closing brace of an interpolated expression.
+    fn string(&mut self, start: usize, continued: bool) -> Option<Token> {
+        let mut literal: String = String::new();
+        while let Some(c) = self.current() {
+            if c == '"' {
+                break;
+            }
+            if c == '$' && self.peek(1) == Some('{') {
+                self.advance();
+                self.advance();
+                let kind: TokenKind = if continued {
+                    TokenKind::StringMiddle
+                } else {
+                    self.interpolations.push((start, 0));
+                    TokenKind::StringStart
+                };
+                return Some(self.token(kind, Some(literal), start));
+            }
+            let escape_start: usize = self.offset();
+            self.advance();
+            if c != '\\' {
+                literal.push(c);
+            } else if let Some(escaped) = self.escape(escape_start) {
+                literal.push(escaped);
+            }
+        }
+        let string_start: usize = if continued { self.interpolations.pop().unwrap().0 } else { start };
+        if self.current().is_none() {
+            self.error(LexErrorKind::UnterminatedString, "unterminated string literal".to_string(), string_start);
+            return None;
+        }
+        self.advance();
+        let kind: TokenKind = if continued { TokenKind::StringEnd } else { TokenKind::StringLiteral };
+        Some(self.token(kind, Some(literal), start))
+    }
+
+    // Lexes the contents of a raw string (`r#"..."#` or `"""..."""`) up to `terminator`. Nothing is
+    // escaped or interpolated and newlines are kept, so the contents are exactly what was written.
+    fn raw_string(&mut self, start: usize, terminator: &str) -> Option<Token> {
+        let contents_start: usize = self.offset();
+        let Some(length) = self.source[contents_start..].find(terminator) else {
+            while self.advance().is_some() {}
+            self.error(LexErrorKind::UnterminatedString, "unterminated raw string literal".to_string(), start);
+            return None;
+        };
+        while self.offset() < contents_start + length + terminator.len() {
+            self.advance();
+        }
+        let literal: String = self.source[contents_start..contents_start + length].to_string();
+        Some(self.token(TokenKind::StringLiteral, Some(literal), start))
+    }
+
+    // Spans always run from `start` up to (but not including) the current character.
+    fn span(&mut self, start: usize) -> Span {
+        Span::new(self.file, start, self.offset())
+    }
+    fn token(&mut self, kind: TokenKind, literal: Option<String>, start: usize) -> Token {
+        Token { kind, literal, span: self.span(start) }
+    }
+    fn single(&mut self, kind: TokenKind, start: usize) -> Token {
+        self.advance();
+        self.token(kind, None, start)
+    }
+    // Lexes the longest run of operator characters (so `<>` is one token and not `<` then `>`).
+    // Runs that spell a built-in operator get its own kind, anything else is a user `Operator`.
+    fn operator(&mut self, start: usize) -> Token {
+        // `<:` and `>:` bound generic parameters, a ':' can't be part of an operator otherwise.
+        if matches!(self.current(), Some('<' | '>')) && self.peek(1) == Some(':') {
+            let kind: TokenKind = if self.advance() == Some('<') { TokenKind::LessColon } else { TokenKind::GreaterColon };
+            return self.single(kind, start);
+        }
+        while self.current().is_some_and(is_operator_character) {
+            if self.current() == Some('/') && matches!(self.peek(1), Some('/' | '*')) {
+                break;
+            }
+            self.advance();
+        }
+        let symbol: &str = &self.source[start..self.offset()];
+        let kind: TokenKind = match symbol {
+            "=" => TokenKind::Equal,
+            "|" => TokenKind::Pipe,
+            "->" => TokenKind::Arrow,
+            "+" => TokenKind::Plus,
+            "-" => TokenKind::Minus,
+            "*" => TokenKind::Asterisk,
+            "/" => TokenKind::Slash,
+            "%" => TokenKind::Percent,
+            "==" => TokenKind::EqualEqual,
+            "!=" => TokenKind::BangEqual,
+            "<" => TokenKind::Less,
+            "<=" => TokenKind::LessEqual,
+            ">" => TokenKind::Greater,
+            ">=" => TokenKind::GreaterEqual,
+            "&&" => TokenKind::AmpersandAmpersand,
+            "||" => TokenKind::PipePipe,
+            "!" => TokenKind::Bang,
+            _ => {
+                let symbol: String = symbol.to_string();
+                return self.token(TokenKind::Operator, Some(symbol), start);
+            }
+        };
+        self.token(kind, None, start)
+    }
+    fn error(&mut self, kind: LexErrorKind, message: String, start: usize) {
+        let span: Span = self.span(start);
+        self.errors.push(LexError { kind, message, span });
+    }
+    // Skips a `/* ... */` comment whose opening `/*` has been consumed. Block comments nest,
+    // so commenting out code that already contains one works as expected.
+    fn block_comment(&mut self, start: usize) {
+        let mut depth: usize = 1;
+        while depth > 0 {
+            match self.advance() {
+                Some('/') if self.current() == Some('*') => {
                     self.advance();
-                    self.start += 1;
-                    if self.current() == '>' {
-                        self.advance();
-                        self.start += 1;
-                        self.end = self.start;
-                        self.tokens.push(Token { kind: TokenKind::Arrow, literal: None, span: Span { start, end: self.end }})
-                    } else {
-                        self.end = self.start;
-                        self.tokens.push(Token { kind: TokenKind::Minus, literal: None, span: Span { start, end: self.end }})
-                    }
+                    depth += 1;
                 }
-                '*' => {
-                    let start: usize = self.start;
+                Some('*') if self.current() == Some('/') => {
                     self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::Asterisk, literal: None, span: Span { start, end: self.end }})
+                    depth -= 1;
                 }
-                '/' => {
-                    let start: usize = self.start;
-                    self.advance();
-                    self.start += 1;
-                    if self.current() == '/' {
-                        self.advance();
-                        self.start += 1;
-                        while self.current < self.source.len() && self.current() != '\n' {
-                            self.advance();
-                            self.start += 1;
-                        }
-                    } else {
-                        self.end = self.start;
-                        self.tokens.push(Token { kind: TokenKind::Slash, literal: None, span: Span { start, end: self.end }})
-                    }
+                Some(_) => {}
+                None => {
+                    self.error(LexErrorKind::UnterminatedComment, "unterminated block comment".to_string(), start);
+                    return;
+                }
+            }
+        }
+    }
+    // Decodes the escape sequence after a backslash, `start` being the backslash's offset.
+    // Invalid escapes are reported and decode to nothing.
+    fn escape(&mut self, start: usize) -> Option<char> {
+        let c: char = self.advance()?;
+        match c {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '0' => Some('\0'),
+            '\\' => Some('\\'),
+            '"' => Some('"'),
+            '\'' => Some('\''),
+            '$' => Some('$'),
+            'u' => {
+                if self.current() != Some('{') {
+                    self.error(LexErrorKind::InvalidEscape, "expected '{' after '\\u'".to_string(), start);
+                    return None;
                 }
-                '%' => {
-                    let start: usize = self.start;
+                self.advance();
+                let digits_start: usize = self.offset();
+                while self.current().is_some_and(|c| c.is_ascii_hexdigit()) {
                     self.advance();
-                    self.start += 1;
-                    self.end = self.start;
-                    self.tokens.push(Token { kind: TokenKind::Percent, literal: None, span: Span { start, end: self.end }})
                 }
-                _ => panic!("Unexpected token '{}'", self.current())
+                let digits: &str = &self.source[digits_start..self.offset()];
+                if self.current() != Some('}') {
+                    self.error(LexErrorKind::InvalidEscape, "unterminated unicode escape".to_string(), start);
+                    return None;
+                }
+                let value: Option<char> = u32::from_str_radix(digits, 16).ok().and_then(char::from_u32);
+                self.advance();
+                if value.is_none() {
+                    self.error(LexErrorKind::InvalidEscape, format!("invalid unicode escape '\\u{{{}}}'", digits), start);
+                }
+                value
+            }
+            c => {
+                self.error(LexErrorKind::InvalidEscape, format!("unknown escape sequence '\\{}'", c), start);
+                None
             }
         }
-        self.tokens.clone()
     }
-
-    fn current(&self) -> char {
-        self.source.chars().nth(self.current).unwrap()
+    fn digits(&mut self) {
+        while self.current().is_some_and(|c| c.is_ascii_digit() || c == '_') {
+            self.advance();
+        }
+    }
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map(|(i, _)| *i).unwrap_or(self.source.len())
     }
-    fn advance(&mut self) -> char {
-        self.current += 1;
-        self.source.chars().nth(self.current - 1).unwrap()
+    fn current(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+    // Looks `n` characters past the current one without consuming anything.
+    fn peek(&mut self, n: usize) -> Option<char> {
+        let offset: usize = self.offset();
+        self.source[offset..].chars().nth(n)
+    }
+    fn advance(&mut self) -> Option<char> {
+        self.chars.next().map(|(_, c)| c)
+    }
+}
+
+// Characters that can make up an operator, built-in or user-defined.
+pub fn is_operator_character(c: char) -> bool {
+    matches!(c, '+' | '-' | '*' | '/' | '%' | '<' | '>' | '=' | '!' | '&' | '|' | '^' | '~' | '@' | '$')
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        self.next_token()
     }
 }
\ No newline at end of file
@@ -1,3 +1,6 @@
 pub mod span;
 pub mod tokens;
-pub mod lexer;
\ No newline at end of file
+pub mod error;
+pub mod literal;
+pub mod lexer;
+pub mod layout;
\ No newline at end of file
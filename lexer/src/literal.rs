@@ -0,0 +1,107 @@
+use crate::error::LexErrorKind;
+
+// Explicit width of an integer literal, as in `255u8`. Unsuffixed literals are `Int`s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntegerSuffix {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntegerSuffix {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "i8" => Some(IntegerSuffix::I8),
+            "i16" => Some(IntegerSuffix::I16),
+            "i32" => Some(IntegerSuffix::I32),
+            "i64" => Some(IntegerSuffix::I64),
+            "u8" => Some(IntegerSuffix::U8),
+            "u16" => Some(IntegerSuffix::U16),
+            "u32" => Some(IntegerSuffix::U32),
+            "u64" => Some(IntegerSuffix::U64),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntegerSuffix::I8 => "i8",
+            IntegerSuffix::I16 => "i16",
+            IntegerSuffix::I32 => "i32",
+            IntegerSuffix::I64 => "i64",
+            IntegerSuffix::U8 => "u8",
+            IntegerSuffix::U16 => "u16",
+            IntegerSuffix::U32 => "u32",
+            IntegerSuffix::U64 => "u64",
+        }
+    }
+    pub fn is_signed(&self) -> bool {
+        matches!(self, IntegerSuffix::I8 | IntegerSuffix::I16 | IntegerSuffix::I32 | IntegerSuffix::I64)
+    }
+    pub fn max(&self) -> u128 {
+        match self {
+            IntegerSuffix::I8 => i8::MAX as u128,
+            IntegerSuffix::I16 => i16::MAX as u128,
+            IntegerSuffix::I32 => i32::MAX as u128,
+            IntegerSuffix::I64 => i64::MAX as u128,
+            IntegerSuffix::U8 => u8::MAX as u128,
+            IntegerSuffix::U16 => u16::MAX as u128,
+            IntegerSuffix::U32 => u32::MAX as u128,
+            IntegerSuffix::U64 => u64::MAX as u128,
+        }
+    }
+}
+
+// The largest value of an integer literal's type, `Int` when it has no suffix.
+pub fn integer_max(suffix: Option<IntegerSuffix>) -> u128 {
+    suffix.map_or(i32::MAX as u128, |suffix| suffix.max())
+}
+
+// Decodes an integer literal such as `42`, `0xFF_u8`, `0b1010` or `1_000_000`, checking that
+// its digits are valid for the radix and that the value fits its type (`Int` when unsuffixed).
+// A signed literal may be one past the largest value, as `-2147483648` is a minus sign applied
+// to `2147483648`, and the parser checks that it is negated.
+pub fn parse_integer(literal: &str) -> Result<(u64, Option<IntegerSuffix>), (LexErrorKind, String)> {
+    let (radix, name, body): (u32, &str, &str) = match literal.get(..2) {
+        Some("0x") | Some("0X") => (16, "hexadecimal", &literal[2..]),
+        Some("0b") | Some("0B") => (2, "binary", &literal[2..]),
+        Some("0o") | Some("0O") => (8, "octal", &literal[2..]),
+        _ => (10, "decimal", literal),
+    };
+    let digits_end: usize = body.find(|c: char| !(c.is_digit(radix) || c == '_')).unwrap_or(body.len());
+    let (digits, suffix) = body.split_at(digits_end);
+    let digits: String = digits.chars().filter(|c| *c != '_').collect();
+    if digits.is_empty() {
+        return Err((LexErrorKind::InvalidLiteral, format!("expected {} digits in integer literal '{}'", name, literal)));
+    }
+    let suffix: Option<IntegerSuffix> = match suffix {
+        "" => None,
+        suffix if suffix.starts_with(|c: char| c.is_ascii_digit()) => {
+            return Err((LexErrorKind::InvalidLiteral, format!("invalid digit '{}' in {} literal '{}'", &suffix[..1], name, literal)));
+        }
+        suffix => match IntegerSuffix::from_name(suffix) {
+            Some(suffix) => Some(suffix),
+            None => return Err((LexErrorKind::InvalidLiteral, format!("invalid suffix '{}' on integer literal '{}'", suffix, literal))),
+        }
+    };
+    let signed: bool = suffix.is_none_or(|suffix| suffix.is_signed());
+    let max: u128 = integer_max(suffix) + signed as u128;
+    match u128::from_str_radix(&digits, radix) {
+        Ok(value) if value <= max => Ok((value as u64, suffix)),
+        _ => Err((LexErrorKind::LiteralOutOfRange, format!("integer literal '{}' is out of range for {}", literal, suffix.map_or("Int", |suffix| suffix.name())))),
+    }
+}
+
+// Decodes a float literal such as `1.5`, `2e10` or `1_000.25`.
+pub fn parse_float(literal: &str) -> Result<f64, (LexErrorKind, String)> {
+    let digits: String = literal.chars().filter(|c| *c != '_').collect();
+    match digits.parse::<f64>() {
+        Ok(value) if value.is_infinite() => Err((LexErrorKind::LiteralOutOfRange, format!("float literal '{}' is out of range", literal))),
+        Ok(value) => Ok(value),
+        Err(_) => Err((LexErrorKind::InvalidLiteral, format!("invalid float literal '{}'", literal))),
+    }
+}
\ No newline at end of file
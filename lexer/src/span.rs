@@ -1,5 +1,92 @@
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId(pub usize);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Span {
+    pub file: FileId,
     pub start: usize,
     pub end: usize,
+}
+
+impl Span {
+    pub fn new(file: FileId, start: usize, end: usize) -> Self {
+        Self { file, start, end }
+    }
+    // Smallest span covering both `self` and `other`, both must be from the same file.
+    pub fn to(&self, other: &Span) -> Span {
+        Span {
+            file: self.file,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub line: usize,    // 1-based
+    pub column: usize,  // 1-based, counted in characters rather than bytes
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub name: String,
+    pub source: String,
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(name: String, source: String) -> Self {
+        let mut line_starts: Vec<usize> = vec![0];
+        for (i, b) in source.bytes().enumerate() {
+            if b == b'\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        Self { name, source, line_starts }
+    }
+    // `offset` is a byte offset into the file, as stored in every `Span`.
+    pub fn location(&self, offset: usize) -> Location {
+        let line: usize = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+        let line_start: usize = self.line_starts[line];
+        let column: usize = self.source.get(line_start..offset).map_or(offset - line_start, |text| text.chars().count());
+        Location { line: line + 1, column: column + 1 }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+    pub fn add_file(&mut self, name: String, source: String) -> FileId {
+        self.files.push(SourceFile::new(name, source));
+        FileId(self.files.len() - 1)
+    }
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0]
+    }
+    pub fn locations(&self, span: &Span) -> (Location, Location) {
+        let file: &SourceFile = self.file(span.file);
+        (file.location(span.start), file.location(span.end))
+    }
+    // Formats a span as `file:line:col`, or `file:line:col-line:col` (end inclusive) for longer spans.
+    pub fn display(&self, span: &Span) -> String {
+        let file: &SourceFile = self.file(span.file);
+        let start: Location = file.location(span.start);
+        let end: Location = file.location(span.end.max(span.start + 1) - 1);
+        let name: &str = &file.name;
+        if start == end {
+            format!("{}:{}:{}", name, start.line, start.column)
+        } else {
+            format!("{}:{}:{}-{}:{}", name, start.line, start.column, end.line, end.column)
+        }
+    }
 }
\ No newline at end of file
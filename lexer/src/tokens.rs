@@ -5,15 +5,26 @@ pub enum TokenKind {
     // Literals
     Identifier,         // foo, bar, baz, etc.
     IntegerLiteral,     // 1234
-    StringLiteral,      // "Hello, World!"
+    FloatLiteral,       // 1.5, 2e10
+    StringLiteral,      // "Hello, World!", r#"raw"#, """raw"""
+    CharLiteral,        // 'a', '\n'
+    DocComment,         // /// Documentation for the next declaration
+    StringStart,        // "sum is ${      (text before the first interpolated expression)
+    StringMiddle,       // } and ${        (text between two interpolated expressions)
+    StringEnd,          // }!"             (text after the last interpolated expression)
 
     // Keywords
     //   Types
     Unit,               // Unit (void)
     Int,                // Int (32-bit signed integer)
+    Float,              // Float (64-bit floating point number)
     Char,               // Char
     Bool,               // Bool (boolean)
 
+    //   Literals
+    True,               // true
+    False,              // false
+
     //   Objects
     Data,               // data (struct or enum)
     Object,             // object (i think this is a class?)
@@ -24,6 +35,8 @@ pub enum TokenKind {
 
     //   Functions
     Procedure,          // procedure (basically a function)
+    Let,                // let (binds a local variable)
+    In,                 // in (the expression a `let` is in scope for)
 
     // Traits
     Trait,              // trait (define a trait)
@@ -43,10 +56,17 @@ pub enum TokenKind {
     Override,           // override (overrides a virtual object)
     Unsafe,             // unsafe (allows you to write unsafe code blocks (not really blocks but whatever))
 
+    //   Operators
+    Infixl,             // infixl (declares a left-associative operator)
+    Infixr,             // infixr (declares a right-associative operator)
+    Infix,              // infix (declares a non-associative operator)
+
     //   Control Flow
     Match,              // match (pattern matching)
     With,               // with (pattern matching)
     Else,               // else (else statement)
+    If,                 // if (conditional expression)
+    Then,               // then (the branch taken when an `if` condition holds)
 
     //   I don't even know
     Cpp,                // cpp
@@ -61,10 +81,12 @@ pub enum TokenKind {
     Colon,              // :
     Semicolon,          // ;
     Dot,                // .
+    DotDot,             // .. (slice bounds)
     Comma,              // ,
     Pipe,               // |
     QuestionMark,       // ?
     Arrow,              // ->
+    Backslash,          // \ (starts a lambda)
 
     // Operators
     Equal,              // =
@@ -75,10 +97,113 @@ pub enum TokenKind {
     Asterisk,           // *
     Slash,              // /
     Percent,            // %
+    EqualEqual,         // ==
+    BangEqual,          // !=
+    Less,               // <
+    LessEqual,          // <=
+    Greater,            // >
+    GreaterEqual,       // >=
+    AmpersandAmpersand, // &&
+    PipePipe,           // ||
+    Bang,               // !
+    Operator,           // <>, |>, etc. (any other run of operator characters, see `infixl`)
 
     // Special
     Newline,
+    Indent,             // start of a more indented block (emitted by the layout pass)
+    Dedent,             // end of an indented block (emitted by the layout pass)
     EndOfLine,
+    EndOfFile,          // end of the token stream (never lexed, the parser reports it past the last token)
+}
+
+impl TokenKind {
+    // How the token is named in diagnostics, like "`(`" or "identifier".
+    pub fn describe(&self) -> &'static str {
+        match self {
+            TokenKind::Identifier => "identifier",
+            TokenKind::IntegerLiteral => "integer literal",
+            TokenKind::FloatLiteral => "float literal",
+            TokenKind::StringLiteral => "string literal",
+            TokenKind::CharLiteral => "character literal",
+            TokenKind::DocComment => "doc comment",
+            TokenKind::StringStart => "interpolated string",
+            TokenKind::StringMiddle => "`}` of an interpolation",
+            TokenKind::StringEnd => "`}` of an interpolation",
+            TokenKind::Unit => "`Unit`",
+            TokenKind::Int => "`Int`",
+            TokenKind::Float => "`Float`",
+            TokenKind::Char => "`Char`",
+            TokenKind::Bool => "`Bool`",
+            TokenKind::True => "`true`",
+            TokenKind::False => "`false`",
+            TokenKind::Data => "`data`",
+            TokenKind::Object => "`object`",
+            TokenKind::Alias => "`alias`",
+            TokenKind::Const => "`const`",
+            TokenKind::Procedure => "`procedure`",
+            TokenKind::Let => "`let`",
+            TokenKind::In => "`in`",
+            TokenKind::Trait => "`trait`",
+            TokenKind::Of => "`of`",
+            TokenKind::Module => "`module`",
+            TokenKind::Import => "`import`",
+            TokenKind::Exposing => "`exposing`",
+            TokenKind::External => "`external`",
+            TokenKind::Internal => "`internal`",
+            TokenKind::Public => "`public`",
+            TokenKind::Private => "`private`",
+            TokenKind::Virtual => "`virtual`",
+            TokenKind::Override => "`override`",
+            TokenKind::Unsafe => "`unsafe`",
+            TokenKind::Infixl => "`infixl`",
+            TokenKind::Infixr => "`infixr`",
+            TokenKind::Infix => "`infix`",
+            TokenKind::Match => "`match`",
+            TokenKind::With => "`with`",
+            TokenKind::Else => "`else`",
+            TokenKind::If => "`if`",
+            TokenKind::Then => "`then`",
+            TokenKind::Cpp => "`cpp`",
+            TokenKind::OpenParenthesis => "`(`",
+            TokenKind::CloseParenthesis => "`)`",
+            TokenKind::OpenBracket => "`[`",
+            TokenKind::CloseBracket => "`]`",
+            TokenKind::OpenBrace => "`{`",
+            TokenKind::CloseBrace => "`}`",
+            TokenKind::Colon => "`:`",
+            TokenKind::Semicolon => "`;`",
+            TokenKind::Dot => "`.`",
+            TokenKind::DotDot => "`..`",
+            TokenKind::Comma => "`,`",
+            TokenKind::Pipe => "`|`",
+            TokenKind::QuestionMark => "`?`",
+            TokenKind::Arrow => "`->`",
+            TokenKind::Backslash => "`\\`",
+            TokenKind::Equal => "`=`",
+            TokenKind::LessColon => "`<:`",
+            TokenKind::GreaterColon => "`>:`",
+            TokenKind::Plus => "`+`",
+            TokenKind::Minus => "`-`",
+            TokenKind::Asterisk => "`*`",
+            TokenKind::Slash => "`/`",
+            TokenKind::Percent => "`%`",
+            TokenKind::EqualEqual => "`==`",
+            TokenKind::BangEqual => "`!=`",
+            TokenKind::Less => "`<`",
+            TokenKind::LessEqual => "`<=`",
+            TokenKind::Greater => "`>`",
+            TokenKind::GreaterEqual => "`>=`",
+            TokenKind::AmpersandAmpersand => "`&&`",
+            TokenKind::PipePipe => "`||`",
+            TokenKind::Bang => "`!`",
+            TokenKind::Operator => "operator",
+            TokenKind::Newline => "newline",
+            TokenKind::Indent => "indented block",
+            TokenKind::Dedent => "end of block",
+            TokenKind::EndOfLine => "end of line",
+            TokenKind::EndOfFile => "end of file",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
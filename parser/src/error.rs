@@ -0,0 +1,29 @@
+use lexer::{
+    span::Span,
+    tokens::{Token, TokenKind}
+};
+
+#[derive(Debug, Clone)] pub struct ParseError {
+    pub message: String,
+    pub expected: Vec<TokenKind>,
+    pub found: Token,
+    pub span: Span,
+}
+impl ParseError {
+    // An error for finding `found` where one of `expected` should have been.
+    pub fn unexpected(expected: Vec<TokenKind>, found: Token) -> Self {
+        let names: Vec<&str> = expected.iter().map(|kind| kind.describe()).collect();
+        let message: String = match names.split_last() {
+            Some((last, [])) => format!("expected {}, found {}", last, found.kind.describe()),
+            Some((last, rest)) => format!("expected {} or {}, found {}", rest.join(", "), last, found.kind.describe()),
+            None => format!("unexpected {}", found.kind.describe()),
+        };
+        let span: Span = found.span.clone();
+        Self { message, expected, found, span }
+    }
+    // An error at `found` that isn't about a missing token, like a duplicate access flag.
+    pub fn invalid(message: String, found: Token) -> Self {
+        let span: Span = found.span.clone();
+        Self { message, expected: vec![], found, span }
+    }
+}
@@ -1 +1,2 @@
+pub mod error;
 pub mod parser;
\ No newline at end of file
@@ -1,23 +1,81 @@
 use std::collections::HashMap;
+use crate::error::ParseError;
 use lexer::{
-    span::Span,
+    literal::{self, IntegerSuffix},
+    span::{FileId, Span},
     tokens::{Token, TokenKind}
 };
 
+#[derive(Debug, Clone)] pub enum StringPart {
+    Text(String),
+    Expression(Expression),
+}
 #[derive(Debug, Clone)] pub struct MatchCase {
-    pub condition: Vec<Expression>,
+    pub pattern: Pattern,
+    // `| pattern if guard -> ...` only matches when the guard holds too.
+    pub guard: Option<Box<Expression>>,
     pub body: Vec<Statement>,
     pub span: Span,
 }
+#[derive(Debug, Clone)] pub enum Pattern {
+    // `_` matches anything without binding it.
+    Wildcard(Span),
+    Binding(String, Span),
+    // An integer, float, char, string or bool, compared with `==`.
+    Literal(Box<Expression>, Span),
+    // `Variant` or `Variant(a, b)` of a data enum, or the fields of a data struct in order.
+    Constructor(String, Vec<Pattern>, Span),
+    // `Struct(field = pattern, other)`, a field on its own binds a variable of the same name.
+    Struct(String, Vec<(String, Pattern)>, Span),
+    // `name @ pattern` binds the whole value while matching it against `pattern`.
+    As(String, Box<Pattern>, Span),
+    // `A | B`, every alternative has to bind the same names.
+    Or(Vec<Pattern>, Span),
+    // `(a, b)`, one pattern for each element of a tuple.
+    Tuple(Vec<Pattern>, Span),
+}
+impl Pattern {
+    // The names the pattern binds, in order.
+    pub fn bindings(&self) -> Vec<String> {
+        match self {
+            Pattern::Wildcard(_) | Pattern::Literal(_, _) => vec![],
+            Pattern::Binding(name, _) => vec![name.clone()],
+            Pattern::Constructor(_, args, _) | Pattern::Tuple(args, _) => args.iter().flat_map(|arg| arg.bindings()).collect(),
+            Pattern::Struct(_, fields, _) => fields.iter().flat_map(|(_, pattern)| pattern.bindings()).collect(),
+            Pattern::As(name, pattern, _) => {
+                let mut names: Vec<String> = vec![name.clone()];
+                names.extend(pattern.bindings());
+                names
+            }
+            Pattern::Or(alternatives, _) => alternatives[0].bindings(),
+        }
+    }
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Wildcard(span) | Pattern::Binding(_, span) | Pattern::Literal(_, span) |
+            Pattern::Constructor(_, _, span) | Pattern::Struct(_, _, span) | Pattern::As(_, _, span) |
+            Pattern::Or(_, span) | Pattern::Tuple(_, span) => span.clone(),
+        }
+    }
+}
 #[derive(Debug, Clone)] pub struct Parser {
     tokens: Vec<Token>,
     statements: Vec<Statement>,
+    errors: Vec<ParseError>,
     current: usize,
+    // How many indented blocks the current token is nested in, used to resynchronise after errors.
+    depth: usize,
 
     current_generic_parameters: Vec<Type>,
+    // Procedures without a body are signatures inside a trait, and an error anywhere else.
+    in_trait: bool,
 
     data_enums: HashMap<String, Vec<EnumVarient>>,
     data_structs: HashMap<String, Vec<(String, Type)>>,
+    // Every data struct in the file, and every data struct and procedure that takes type
+    // arguments, found before parsing so they can be used before their declarations.
+    struct_names: Vec<String>,
+    generic_names: Vec<String>,
     aliases: HashMap<String, Type>,
     objects: HashMap<String, (Vec<Type>, Vec<Statement>)>,
     procedures: HashMap<String, (Vec<(String, Type)>, Type, Expression)>,
@@ -28,6 +86,9 @@ use lexer::{
     generic_aliases: HashMap<String, Vec<(Type, GenericType, Vec<Type>)>>,
     generic_objects: HashMap<String, Vec<(Type, GenericType, Vec<Type>)>>,
     generic_procedures: HashMap<String, Vec<(Type, GenericType, Vec<Type>)>>,
+
+    // Precedence (0 to 9, higher binds tighter) and associativity of every binary operator.
+    operators: HashMap<String, (u8, Associativity)>,
 }
 
 #[derive(Debug, Clone, PartialEq)] pub enum EnumVarient {
@@ -40,6 +101,11 @@ use lexer::{
     Extends,
     None,
 }
+#[derive(Debug, Clone, Copy, PartialEq)] pub enum Associativity {
+    Left,
+    Right,
+    None,
+}
 #[derive(Debug, Clone, PartialEq)] pub enum AccessFlag {
     External,
     Internal,
@@ -51,6 +117,7 @@ use lexer::{
 #[derive(Debug, Clone, PartialEq)] pub enum Type {
     Unit(Span),
     Int(Span),
+    Float(Span),
     Char(Span),
     Bool(Span),
 
@@ -67,6 +134,8 @@ use lexer::{
     Array(Box<Type>, Span),
 
     Function(Vec<Type>, Box<Type>, Span),
+    // `(Int, String)`, told apart from a function type by the missing `->`.
+    Tuple(Vec<Type>, Span),
 
     Unknown(String, Span),
 }
@@ -77,14 +146,16 @@ use lexer::{
     Object(String, Vec<AccessFlag>, Vec<Type>, Vec<(String, Type)>, Vec<Statement>, Span),
     Procedure(String, Vec<AccessFlag>, Vec<(String, Type)>, Type, Expression, Span),
     Const(String, Vec<AccessFlag>, Type, Expression, Span),
-    Trait(String, Vec<AccessFlag>, Span),
+    Trait(String, Vec<AccessFlag>, Vec<Statement>, Span),
+    // A procedure without a body, only allowed as a required member of a trait.
+    Signature(String, Vec<AccessFlag>, Vec<(String, Type)>, Type, Span),
     
     GenericDataEnum(String, Vec<AccessFlag>, Vec<(Type, GenericType, Vec<Type>)>, Vec<EnumVarient>, Span),
     GenericDataStruct(String, Vec<AccessFlag>, Vec<(Type, GenericType, Vec<Type>)>, Vec<(String, Type)>, Span),
     GenericAlias(String, Vec<AccessFlag>, Vec<(Type, GenericType, Vec<Type>)>, Type, Span),
     GenericObject(String, Vec<AccessFlag>, Vec<(Type, GenericType, Vec<Type>)>, Vec<Type>, Vec<(String, Type)>, Vec<Statement>, Span),
     GenericProcedure(String, Vec<AccessFlag>, Vec<(Type, GenericType, Vec<Type>)>, Vec<(String, Type)>, Type, Expression, Span),
-    GenericTrait(String, Vec<AccessFlag>, Vec<(Type, GenericType, Vec<Type>)>, Span),
+    GenericTrait(String, Vec<AccessFlag>, Vec<(Type, GenericType, Vec<Type>)>, Vec<Statement>, Span),
 
     Module(Expression, Span),
     Import(Expression, Span),
@@ -92,26 +163,104 @@ use lexer::{
 
     Of(Type, Span),
 
+    // `infixl 6 <>`, only affects how the rest of the file is parsed.
+    Fixity(Vec<String>, Associativity, u8, Span),
+
     Expression(Expression, Span),
+    // `let name: Type = value` inside a block, the type is optional.
+    Let(String, Option<Type>, Expression, Span),
+    // `let (a, b) = value`, the pattern has to match every value.
+    Destructure(Pattern, Option<Type>, Expression, Span),
+
+    // A declaration preceded by `///` doc comments, the lines of which are joined by '\n'.
+    Documented(String, Box<Statement>, Span),
+}
+impl Statement {
+    // The statement itself, looking through any doc comments attached to it.
+    pub fn undocumented(&self) -> &Statement {
+        match self {
+            Statement::Documented(_, statement, _) => statement.undocumented(),
+            _ => self,
+        }
+    }
 }
 #[derive(Debug, Clone)] pub enum Expression {
-    Member(Box<Expression>, Box<Expression>, Span),
-    Call(String, Vec<Expression>, Span),
+    // `value.field`, also how the parts of a module path are joined.
+    Member(Box<Expression>, String, Span),
+    // `callee[T, U](args)` calls any expression, like `make_adder(1)(2)`, with optional
+    // explicit type arguments for a generic procedure.
+    Call(Box<Expression>, Vec<Type>, Vec<Expression>, Span),
+    // `receiver.method[T](args)`.
+    MethodCall(Box<Expression>, String, Vec<Type>, Vec<Expression>, Span),
+    // `Struct[T](a = 1, 2)` constructs a data struct, fields can be given by name or in order.
+    Construct(String, Vec<Type>, Vec<(Option<String>, Expression)>, Span),
+    // `value with { field = new_value }`, a copy of a data struct with some fields replaced.
+    With(Box<Expression>, Vec<(String, Expression)>, Span),
+    // `xs[i]`, or `m[key]` which gives an optional for a map.
+    Index(Box<Expression>, Box<Expression>, Span),
+    // `xs[start..end]`, either bound can be left out.
+    Slice(Box<Expression>, Option<Box<Expression>>, Option<Box<Expression>>, Span),
+    // `\x, y: Int -> x + y`, parameters without a type are inferred by C++.
+    Lambda(Vec<(String, Option<Type>)>, Box<Expression>, Span),
     Variable(String, Span),
     String(String, Span),
-    Integer(i64, Span),
+    Interpolated(Vec<StringPart>, Span),
+    Char(char, Span),
+    Bool(bool, Span),
+    Integer(u64, Option<IntegerSuffix>, Span),
+    Float(f64, Span),
     Match(Box<Expression>, Vec<MatchCase>, Option<MatchCase>, Span),
+    // `if condition then a else b`, an `else if` chain nests in the else branch.
+    If(Box<Expression>, Box<Expression>, Box<Expression>, Span),
     Binary(Box<Expression>, Box<Expression>, TokenKind, Span),
+    Unary(Box<Expression>, TokenKind, Span),
     Unsafe(Box<Expression>, Span),
     Cpp(String, Span),
     List(Vec<Expression>, Span),
     Map(Vec<(Expression, Expression)>, Span),
+    // `(a, b)`, at least two elements as `(a)` is just `a`.
+    Tuple(Vec<Expression>, Span),
+    // `let` bindings followed by the expression they are in scope for.
+    Block(Vec<Statement>, Box<Expression>, Span),
 }
 impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Member(_, _, span) | Expression::Call(_, _, _, span) | Expression::MethodCall(_, _, _, _, span) |
+            Expression::Index(_, _, span) | Expression::Slice(_, _, _, span) |
+            Expression::Construct(_, _, _, span) | Expression::With(_, _, span) |
+            Expression::Lambda(_, _, span) | Expression::Variable(_, span) | Expression::String(_, span) |
+            Expression::Interpolated(_, span) | Expression::Char(_, span) | Expression::Bool(_, span) |
+            Expression::Integer(_, _, span) | Expression::Float(_, span) | Expression::Match(_, _, _, span) |
+            Expression::If(_, _, _, span) | Expression::Binary(_, _, _, span) | Expression::Unary(_, _, span) |
+            Expression::Unsafe(_, span) | Expression::Cpp(_, span) | Expression::List(_, span) |
+            Expression::Map(_, span) | Expression::Tuple(_, span) | Expression::Block(_, _, span) => span.clone(),
+        }
+    }
     pub fn get_type(&self) -> Type {
         match self {
             Expression::String(_, span) => Type::Unknown("String".to_string(), span.clone()),
-            Expression::Integer(_, span) => Type::Int(span.clone()),
+            Expression::Interpolated(_, span) => Type::Unknown("String".to_string(), span.clone()),
+            Expression::Char(_, span) => Type::Char(span.clone()),
+            Expression::Bool(_, span) => Type::Bool(span.clone()),
+            // Int is the only integer type, suffixed literals just pick a different C++ width.
+            Expression::Integer(_, _, span) => Type::Int(span.clone()),
+            Expression::Float(_, span) => Type::Float(span.clone()),
+            Expression::Binary(left, right, op, span) => match op {
+                TokenKind::EqualEqual | TokenKind::BangEqual |
+                TokenKind::Less | TokenKind::LessEqual |
+                TokenKind::Greater | TokenKind::GreaterEqual |
+                TokenKind::AmpersandAmpersand | TokenKind::PipePipe => Type::Bool(span.clone()),
+                // Mixed arithmetic promotes to Float, like C++ does with int and double.
+                _ => match (left.get_type(), right.get_type()) {
+                    (Type::Float(_), _) | (_, Type::Float(_)) => Type::Float(span.clone()),
+                    (t, _) => t,
+                }
+            }
+            Expression::Unary(_, TokenKind::Bang, span) => Type::Bool(span.clone()),
+            Expression::Unary(operand, _, _) => operand.get_type(),
+            Expression::Block(_, result, _) => result.get_type(),
+            Expression::If(_, then_branch, _, _) => then_branch.get_type(),
             Expression::List(items, span) => {
                 if items.len() == 0 {
                     Type::Array(Box::new(Type::Unit(span.clone())), span.clone())
@@ -120,6 +269,7 @@ impl Expression {
                     Type::Array(Box::new(inner_type), span.clone())
                 }
             }
+            Expression::Tuple(items, span) => Type::Tuple(items.iter().map(|item| item.get_type()).collect(), span.clone()),
             _ => unimplemented!("Expression::get_type()"),
         }
     }
@@ -130,12 +280,17 @@ impl Parser {
         Self {
             tokens,
             statements: vec![],
+            errors: vec![],
             current: 0,
+            depth: 0,
 
             current_generic_parameters: vec![],
+            in_trait: false,
 
             data_enums: HashMap::new(),
             data_structs: HashMap::new(),
+            struct_names: vec![],
+            generic_names: vec![],
             aliases: HashMap::new(),
             objects: HashMap::new(),
             procedures: HashMap::new(),
@@ -146,22 +301,128 @@ impl Parser {
             generic_aliases: HashMap::new(),
             generic_objects: HashMap::new(),
             generic_procedures: HashMap::new(),
+
+            operators: HashMap::from([
+                ("||".to_string(), (2, Associativity::Right)),
+                ("&&".to_string(), (3, Associativity::Right)),
+                ("==".to_string(), (4, Associativity::None)),
+                ("!=".to_string(), (4, Associativity::None)),
+                ("<".to_string(), (4, Associativity::None)),
+                ("<=".to_string(), (4, Associativity::None)),
+                (">".to_string(), (4, Associativity::None)),
+                (">=".to_string(), (4, Associativity::None)),
+                ("+".to_string(), (6, Associativity::Left)),
+                ("-".to_string(), (6, Associativity::Left)),
+                ("*".to_string(), (7, Associativity::Left)),
+                ("/".to_string(), (7, Associativity::Left)),
+                ("%".to_string(), (7, Associativity::Left)),
+            ]),
         }
     }
-    pub fn parse(&mut self) -> Vec<Statement> {
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+    pub fn data_enums(&self) -> &HashMap<String, Vec<EnumVarient>> {
+        &self.data_enums
+    }
+    pub fn data_structs(&self) -> &HashMap<String, Vec<(String, Type)>> {
+        &self.data_structs
+    }
+    // Parses every statement it can, skipping to the next declaration after an error so that
+    // one mistake doesn't hide the ones after it.
+    pub fn parse(&mut self) -> (Vec<Statement>, Vec<ParseError>) {
+        self.find_declarations();
         while self.current < self.tokens.len() {
             if self.current().kind == TokenKind::Newline {
                 self.advance();
                 continue;
             }
-            let statement: Statement = self.parse_statement();
-            self.statements.push(statement);
+            let start: usize = self.current;
+            match self.parse_statement() {
+                Ok(statement) => self.statements.push(statement),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.current_generic_parameters.clear();
+                    self.in_trait = false;
+                    self.synchronize(start);
+                }
+            }
         }
-        self.statements.clone()
+        (self.statements.clone(), self.errors.clone())
+    }
+    // Skips tokens until the end of the broken line (and any block hanging off it) or the next
+    // top-level declaration keyword, always moving past the token the statement started at.
+    fn synchronize(&mut self, start: usize) {
+        while self.current < self.tokens.len() {
+            let kind: TokenKind = self.current().kind;
+            if self.depth == 0 && self.current > start && Self::starts_declaration(&kind) {
+                return;
+            }
+            self.advance();
+            let line_ended: bool = kind == TokenKind::Newline || kind == TokenKind::Dedent;
+            if self.depth == 0 && line_ended && self.current().kind != TokenKind::Indent {
+                return;
+            }
+        }
+    }
+    // Looks ahead for the names of every `data Name(...)`, `data Name[T](...)` and `procedure Name[T]`.
+    fn find_declarations(&mut self) {
+        for i in 0..self.tokens.len() {
+            if !matches!(self.tokens[i].kind, TokenKind::Data | TokenKind::Procedure) {
+                continue;
+            }
+            let Some(name) = self.tokens.get(i + 1).filter(|token| token.kind == TokenKind::Identifier) else {
+                continue;
+            };
+            let name: String = name.literal.clone().unwrap();
+            let mut j: usize = i + 2;
+            let generic: bool = self.tokens.get(j).is_some_and(|token| token.kind == TokenKind::OpenBracket);
+            if self.tokens[i].kind == TokenKind::Procedure {
+                if generic {
+                    self.generic_names.push(name);
+                }
+                continue;
+            }
+            if generic {
+                let mut depth: usize = 0;
+                while let Some(token) = self.tokens.get(j) {
+                    match token.kind {
+                        TokenKind::OpenBracket => depth += 1,
+                        TokenKind::CloseBracket if depth == 1 => break,
+                        TokenKind::CloseBracket => depth -= 1,
+                        TokenKind::Newline => break,
+                        _ => {}
+                    }
+                    j += 1;
+                }
+                j += 1;
+            }
+            if self.tokens.get(j).is_some_and(|token| token.kind == TokenKind::OpenParenthesis) {
+                if generic {
+                    self.generic_names.push(name.clone());
+                }
+                self.struct_names.push(name);
+            }
+        }
+    }
+    fn starts_declaration(kind: &TokenKind) -> bool {
+        matches!(kind,
+            TokenKind::Data | TokenKind::Alias | TokenKind::Object | TokenKind::Const |
+            TokenKind::Procedure | TokenKind::Trait | TokenKind::Module | TokenKind::Import |
+            TokenKind::Infixl | TokenKind::Infixr | TokenKind::Infix |
+            TokenKind::Public | TokenKind::Private | TokenKind::External | TokenKind::Internal |
+            TokenKind::Virtual | TokenKind::Override | TokenKind::DocComment
+        )
     }
 
-    fn parse_statement(&mut self) -> Statement {
-        let access_flags: Vec<AccessFlag> = self.parse_access_flags();
+    fn parse_statement(&mut self) -> Result<Statement, ParseError> {
+        if self.current().kind == TokenKind::DocComment {
+            let span: Span = self.current().span;
+            let doc: String = self.parse_doc_comment()?;
+            let statement: Statement = self.parse_statement()?;
+            return Ok(self.document(doc, statement, span));
+        }
+        let access_flags: Vec<AccessFlag> = self.parse_access_flags()?;
         match self.current().kind {
             TokenKind::Data => self.parse_data(access_flags),
             TokenKind::Alias => self.parse_alias(access_flags),
@@ -171,577 +432,1005 @@ impl Parser {
             TokenKind::Trait => self.parse_trait(access_flags),
             TokenKind::Module => self.parse_module(),
             TokenKind::Import => self.parse_import(),
+            TokenKind::Infixl | TokenKind::Infixr | TokenKind::Infix => self.parse_fixity(),
             _ => {
                 let span: Span = self.current().span;
-                let expression: Expression = self.parse_expression();
-                Statement::Expression(expression, span)
+                let expression: Expression = self.parse_expression()?;
+                Ok(Statement::Expression(expression, span))
             }
         }
     }
-    fn parse_data(&mut self, flags: Vec<AccessFlag>) -> Statement {
-        self.expect(TokenKind::Data);
+    fn parse_data(&mut self, flags: Vec<AccessFlag>) -> Result<Statement, ParseError> {
+        self.expect(TokenKind::Data)?;
         let name_span: Span = self.current().span;
-        let identifier: String = self.expect(TokenKind::Identifier).literal.unwrap();
+        let identifier: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
         let mut generic_parameters: Vec<(Type, GenericType, Vec<Type>)> = vec![];
         if self.current().kind == TokenKind::OpenBracket {
-            self.expect(TokenKind::OpenBracket);
+            self.expect(TokenKind::OpenBracket)?;
             while self.current < self.tokens.len() && self.current().kind != TokenKind::CloseBracket {
-                let t: Type = self.parse_type();
+                let token: Token = self.current();
+                let t: Type = self.parse_type()?;
                 let generic_type: GenericType = match self.current().kind {
                     TokenKind::LessColon => {
-                        self.expect(TokenKind::LessColon);
+                        self.expect(TokenKind::LessColon)?;
                         GenericType::Implements
                     },
                     TokenKind::GreaterColon => {
-                        self.expect(TokenKind::GreaterColon);
+                        self.expect(TokenKind::GreaterColon)?;
                         GenericType::Extends
                     },
                     _ => GenericType::None
                 };
                 let mut types: Vec<Type> = vec![];
                 if generic_type != GenericType::None {
-                    types.push(self.parse_type());
+                    types.push(self.parse_type()?);
                     while self.current().kind == TokenKind::Pipe {
-                        self.expect(TokenKind::Pipe);
-                        types.push(self.parse_type());
+                        self.expect(TokenKind::Pipe)?;
+                        types.push(self.parse_type()?);
                     }
                 }
                 generic_parameters.push((match t {
                     Type::Unknown(id, span) => Type::GenericParameter(id, span),
-                    _ => panic!("Generic parameters must be unknown types")
+                    _ => return Err(ParseError::invalid("generic parameters must be new type names".to_string(), token))
                 }, generic_type, types));
                 if self.current().kind == TokenKind::Comma {
-                    self.expect(TokenKind::Comma);
+                    self.expect(TokenKind::Comma)?;
                 }
             }
-            self.expect(TokenKind::CloseBracket);
+            self.expect(TokenKind::CloseBracket)?;
         }
         if self.current().kind == TokenKind::OpenParenthesis {
-            self.expect(TokenKind::OpenParenthesis);
+            self.expect(TokenKind::OpenParenthesis)?;
             for (generic_type, _, _) in generic_parameters.clone() {
                 self.current_generic_parameters.push(generic_type);
             }
             let mut parameters: Vec<(String, Type)> = vec![];
             while self.current < self.tokens.len() && self.current().kind != TokenKind::CloseParenthesis {
-                let name: String = self.expect(TokenKind::Identifier).literal.unwrap();
-                self.expect(TokenKind::Colon);
-                let t: Type = self.parse_type();
+                let name: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
+                self.expect(TokenKind::Colon)?;
+                let t: Type = self.parse_type()?;
                 parameters.push((name, t));
                 if self.current().kind == TokenKind::Comma {
-                    self.expect(TokenKind::Comma);
+                    self.expect(TokenKind::Comma)?;
                 }
             }
-            self.expect(TokenKind::CloseParenthesis);
-            self.expect(TokenKind::Newline);
+            self.expect(TokenKind::CloseParenthesis)?;
+            self.expect(TokenKind::Newline)?;
             for _ in 0..generic_parameters.len() {
                 self.current_generic_parameters.pop();
             }
             if generic_parameters.len() > 0 {
                 self.data_structs.insert(identifier.clone(), parameters.clone());
                 self.generic_data_structs.insert(identifier.clone(), generic_parameters.clone());
-                Statement::GenericDataStruct(identifier, flags, generic_parameters, parameters, name_span)
+                Ok(Statement::GenericDataStruct(identifier, flags, generic_parameters, parameters, name_span))
             } else {
                 self.data_structs.insert(identifier.clone(), parameters.clone());
-                Statement::DataStruct(identifier, flags, parameters, name_span)
+                Ok(Statement::DataStruct(identifier, flags, parameters, name_span))
             }
         } else {
-            self.expect(TokenKind::Equal);
-            self.expect(TokenKind::Newline);
+            self.expect(TokenKind::Equal)?;
+            self.expect(TokenKind::Newline)?;
+            self.expect(TokenKind::Indent)?;
             for (generic_type, _, _) in generic_parameters.clone() {
                 self.current_generic_parameters.push(generic_type);
             }
             let mut variants: Vec<EnumVarient> = vec![];
             while self.current < self.tokens.len() && self.current().kind == TokenKind::Pipe {
-                self.expect(TokenKind::Pipe);
-                let variant: EnumVarient = self.parse_enum_variant();
+                self.expect(TokenKind::Pipe)?;
+                let variant: EnumVarient = self.parse_enum_variant()?;
                 variants.push(variant);
-                self.expect(TokenKind::Newline);
+                self.expect(TokenKind::Newline)?;
             }
+            self.expect(TokenKind::Dedent)?;
             for _ in 0..generic_parameters.len() {
                 self.current_generic_parameters.pop();
             }
             if generic_parameters.len() > 0 {
                 self.data_enums.insert(identifier.clone(), variants.clone());
                 self.generic_data_enums.insert(identifier.clone(), generic_parameters.clone());
-                Statement::GenericDataEnum(identifier, flags, generic_parameters, variants, name_span)
+                Ok(Statement::GenericDataEnum(identifier, flags, generic_parameters, variants, name_span))
             } else {
                 self.data_enums.insert(identifier.clone(), variants.clone());
-                Statement::DataEnum(identifier, flags, variants, name_span)
+                Ok(Statement::DataEnum(identifier, flags, variants, name_span))
             }
         }
     }
-    fn parse_alias(&mut self, flags: Vec<AccessFlag>) -> Statement {
-        self.expect(TokenKind::Alias);
+    fn parse_alias(&mut self, flags: Vec<AccessFlag>) -> Result<Statement, ParseError> {
+        self.expect(TokenKind::Alias)?;
         let name_span: Span = self.current().span;
-        let identifier: String = self.expect(TokenKind::Identifier).literal.unwrap();
+        let identifier: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
         let mut generic_parameters: Vec<(Type, GenericType, Vec<Type>)> = vec![];
         if self.current().kind == TokenKind::OpenBracket {
-            self.expect(TokenKind::OpenBracket);
+            self.expect(TokenKind::OpenBracket)?;
             while self.current < self.tokens.len() && self.current().kind != TokenKind::CloseBracket {
-                let t: Type = self.parse_type();
+                let token: Token = self.current();
+                let t: Type = self.parse_type()?;
                 let generic_type: GenericType = match self.current().kind {
                     TokenKind::LessColon => {
-                        self.expect(TokenKind::LessColon);
+                        self.expect(TokenKind::LessColon)?;
                         GenericType::Implements
                     },
                     TokenKind::GreaterColon => {
-                        self.expect(TokenKind::GreaterColon);
+                        self.expect(TokenKind::GreaterColon)?;
                         GenericType::Extends
                     },
                     _ => GenericType::None
                 };
                 let mut types: Vec<Type> = vec![];
                 if generic_type != GenericType::None {
-                    types.push(self.parse_type());
+                    types.push(self.parse_type()?);
                     while self.current().kind == TokenKind::Pipe {
-                        self.expect(TokenKind::Pipe);
-                        types.push(self.parse_type());
+                        self.expect(TokenKind::Pipe)?;
+                        types.push(self.parse_type()?);
                     }
                 }
                 generic_parameters.push((match t {
                     Type::Unknown(id, span) => Type::GenericParameter(id, span),
-                    _ => panic!("Generic parameters must be unknown types")
+                    _ => return Err(ParseError::invalid("generic parameters must be new type names".to_string(), token))
                 }, generic_type, types));
                 if self.current().kind == TokenKind::Comma {
-                    self.expect(TokenKind::Comma);
+                    self.expect(TokenKind::Comma)?;
                 }
             }
-            self.expect(TokenKind::CloseBracket);
+            self.expect(TokenKind::CloseBracket)?;
         }
         for (generic_type, _, _) in generic_parameters.clone() {
             self.current_generic_parameters.push(generic_type);
         }
-        self.expect(TokenKind::Equal);
-        let t: Type = self.parse_type();
-        self.expect(TokenKind::Newline);
+        self.expect(TokenKind::Equal)?;
+        let t: Type = self.parse_type()?;
+        self.expect(TokenKind::Newline)?;
         for _ in 0..generic_parameters.len() {
             self.current_generic_parameters.pop();
         }
         if generic_parameters.len() > 0 {
             self.aliases.insert(identifier.clone(), t.clone());
             self.generic_aliases.insert(identifier.clone(), generic_parameters.clone());
-            Statement::GenericAlias(identifier, flags, generic_parameters, t, name_span)
+            Ok(Statement::GenericAlias(identifier, flags, generic_parameters, t, name_span))
         } else {
             self.aliases.insert(identifier.clone(), t.clone());
-            Statement::Alias(identifier, flags, t, name_span)
+            Ok(Statement::Alias(identifier, flags, t, name_span))
         }
     }
-    fn parse_object(&mut self, flags: Vec<AccessFlag>) -> Statement {
-        self.expect(TokenKind::Object);
+    fn parse_object(&mut self, flags: Vec<AccessFlag>) -> Result<Statement, ParseError> {
+        self.expect(TokenKind::Object)?;
         let name_span: Span = self.current().span;
-        let identifier: String = self.expect(TokenKind::Identifier).literal.unwrap();
+        let identifier: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
         let mut generic_parameters: Vec<(Type, GenericType, Vec<Type>)> = vec![];
         if self.current().kind == TokenKind::OpenBracket {
-            self.expect(TokenKind::OpenBracket);
+            self.expect(TokenKind::OpenBracket)?;
             while self.current < self.tokens.len() && self.current().kind != TokenKind::CloseBracket {
-                let t: Type = self.parse_type();
+                let token: Token = self.current();
+                let t: Type = self.parse_type()?;
                 let generic_type: GenericType = match self.current().kind {
                     TokenKind::LessColon => {
-                        self.expect(TokenKind::LessColon);
+                        self.expect(TokenKind::LessColon)?;
                         GenericType::Implements
                     },
                     TokenKind::GreaterColon => {
-                        self.expect(TokenKind::GreaterColon);
+                        self.expect(TokenKind::GreaterColon)?;
                         GenericType::Extends
                     },
                     _ => GenericType::None
                 };
                 let mut types: Vec<Type> = vec![];
                 if generic_type != GenericType::None {
-                    types.push(self.parse_type());
+                    types.push(self.parse_type()?);
                     while self.current().kind == TokenKind::Pipe {
-                        self.expect(TokenKind::Pipe);
-                        types.push(self.parse_type());
+                        self.expect(TokenKind::Pipe)?;
+                        types.push(self.parse_type()?);
                     }
                 }
                 generic_parameters.push((match t {
                     Type::Unknown(id, span) => Type::GenericParameter(id, span),
-                    _ => panic!("Generic parameters must be unknown types")
+                    _ => return Err(ParseError::invalid("generic parameters must be new type names".to_string(), token))
                 }, generic_type, types));
                 if self.current().kind == TokenKind::Comma {
-                    self.expect(TokenKind::Comma);
+                    self.expect(TokenKind::Comma)?;
                 }
             }
-            self.expect(TokenKind::CloseBracket);
+            self.expect(TokenKind::CloseBracket)?;
         }
         let mut parameters: Vec<(String, Type)> = vec![];
         if self.current().kind == TokenKind::OpenParenthesis {
-            self.expect(TokenKind::OpenParenthesis);
+            self.expect(TokenKind::OpenParenthesis)?;
             while self.current().kind != TokenKind::CloseParenthesis {
-                let n: String = self.expect(TokenKind::Identifier).literal.unwrap();
-                self.expect(TokenKind::Colon);
-                let t: Type = self.parse_type();
+                let n: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
+                self.expect(TokenKind::Colon)?;
+                let t: Type = self.parse_type()?;
                 parameters.push((n, t));
                 if self.current().kind == TokenKind::Comma {
-                    self.expect(TokenKind::Comma);
+                    self.expect(TokenKind::Comma)?;
                 }
             }
-            self.expect(TokenKind::CloseParenthesis);
+            self.expect(TokenKind::CloseParenthesis)?;
         }
         let mut parents: Vec<Type> = vec![];
         if self.current().kind == TokenKind::Colon {
-            self.expect(TokenKind::Colon);
-            while self.current().kind != TokenKind::Newline {
-                parents.push(self.parse_type());
+            self.expect(TokenKind::Colon)?;
+            while self.current().kind != TokenKind::Newline && self.current().kind != TokenKind::Equal {
+                parents.push(self.parse_type()?);
                 if self.current().kind == TokenKind::Comma {
-                    self.expect(TokenKind::Comma);
+                    self.expect(TokenKind::Comma)?;
                 }
             }
         }
         if self.current().kind == TokenKind::Equal {
-            self.expect(TokenKind::Equal);
-            self.expect(TokenKind::Newline);
+            self.expect(TokenKind::Equal)?;
+            self.expect(TokenKind::Newline)?;
+            self.expect(TokenKind::Indent)?;
             let mut statements: Vec<Statement> = vec![];
-            while self.current().kind == TokenKind::Pipe {
-                self.expect(TokenKind::Pipe);
-                let access_flags: Vec<AccessFlag> = self.parse_access_flags();
+            while self.current().kind == TokenKind::Pipe || self.current().kind == TokenKind::DocComment {
+                let doc_span: Span = self.current().span;
+                let doc: String = self.parse_doc_comment()?;
+                self.expect(TokenKind::Pipe)?;
+                let access_flags: Vec<AccessFlag> = self.parse_access_flags()?;
                 if self.current().kind == TokenKind::Procedure {
-                    statements.push(self.parse_procedure(access_flags));
+                    let procedure: Statement = self.parse_procedure(access_flags)?;
+                    statements.push(self.document(doc, procedure, doc_span));
                 } else if self.current().kind == TokenKind::Of {
-                    statements.push(self.parse_of());
+                    statements.push(self.parse_of()?);
                 } else {
-                    panic!("Expected Procedure or Of, but got {:?}", self.current().kind);
+                    return Err(self.unexpected(vec![TokenKind::Procedure, TokenKind::Of]));
                 }
             }
-            if self.current().kind == TokenKind::Newline {
-                self.expect(TokenKind::Newline);
-            }
+            self.expect(TokenKind::Dedent)?;
             if generic_parameters.len() > 0 {
                 self.objects.insert(identifier.clone(), (parents.clone(), statements.clone()));
                 self.generic_objects.insert(identifier.clone(), generic_parameters.clone());
-                Statement::GenericObject(identifier, flags, generic_parameters, parents, parameters, statements, name_span)
+                Ok(Statement::GenericObject(identifier, flags, generic_parameters, parents, parameters, statements, name_span))
             } else {
                 self.objects.insert(identifier.clone(), (parents.clone(), statements.clone()));
-                Statement::Object(identifier, flags, parents, parameters, statements, name_span)
+                Ok(Statement::Object(identifier, flags, parents, parameters, statements, name_span))
             }
         } else {
-            self.expect(TokenKind::Newline);
+            self.expect(TokenKind::Newline)?;
             if generic_parameters.len() > 0 {
                 self.objects.insert(identifier.clone(), (parents.clone(), vec![]));
                 self.generic_objects.insert(identifier.clone(), generic_parameters.clone());
-                Statement::GenericObject(identifier, flags, generic_parameters, parents, parameters, vec![], name_span)
+                Ok(Statement::GenericObject(identifier, flags, generic_parameters, parents, parameters, vec![], name_span))
             } else {
                 self.objects.insert(identifier.clone(), (parents.clone(), vec![]));
-                Statement::Object(identifier, flags, parents, parameters, vec![], name_span)
+                Ok(Statement::Object(identifier, flags, parents, parameters, vec![], name_span))
             }
         }
     }
-    fn parse_const(&mut self, flags: Vec<AccessFlag>) -> Statement {
-        self.expect(TokenKind::Const);
+    fn parse_const(&mut self, flags: Vec<AccessFlag>) -> Result<Statement, ParseError> {
+        self.expect(TokenKind::Const)?;
         let name_span: Span = self.current().span;
-        let identifier: String = self.expect(TokenKind::Identifier).literal.unwrap();
-        self.expect(TokenKind::Colon);
-        let t: Type = self.parse_type();
-        self.expect(TokenKind::Equal);
-        let expr: Expression = self.parse_expression();
-        self.expect(TokenKind::Newline);
+        let identifier: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
+        self.expect(TokenKind::Colon)?;
+        let t: Type = self.parse_type()?;
+        self.expect(TokenKind::Equal)?;
+        let expr: Expression = self.parse_expression()?;
+        self.expect_line_end()?;
         self.consts.insert(identifier.clone(), (t.clone(), expr.clone()));
-        Statement::Const(identifier, flags, t, expr, name_span)
+        Ok(Statement::Const(identifier, flags, t, expr, name_span))
     }
-    fn parse_procedure(&mut self, flags: Vec<AccessFlag>) -> Statement {
-        self.expect(TokenKind::Procedure);
+    fn parse_procedure(&mut self, flags: Vec<AccessFlag>) -> Result<Statement, ParseError> {
+        self.expect(TokenKind::Procedure)?;
         let name_span: Span = self.current().span;
-        let identifier: String = self.expect(TokenKind::Identifier).literal.unwrap();
+        let identifier: String = self.parse_procedure_name()?;
         let mut generic_parameters: Vec<(Type, GenericType, Vec<Type>)> = vec![];
         if self.current().kind == TokenKind::OpenBracket {
-            self.expect(TokenKind::OpenBracket);
+            self.expect(TokenKind::OpenBracket)?;
             while self.current < self.tokens.len() && self.current().kind != TokenKind::CloseBracket {
-                let t: Type = self.parse_type();
+                let token: Token = self.current();
+                let t: Type = self.parse_type()?;
                 let generic_type: GenericType = match self.current().kind {
                     TokenKind::LessColon => {
-                        self.expect(TokenKind::LessColon);
+                        self.expect(TokenKind::LessColon)?;
                         GenericType::Implements
                     },
                     TokenKind::GreaterColon => {
-                        self.expect(TokenKind::GreaterColon);
+                        self.expect(TokenKind::GreaterColon)?;
                         GenericType::Extends
                     },
                     _ => GenericType::None
                 };
                 let mut types: Vec<Type> = vec![];
                 if generic_type != GenericType::None {
-                    types.push(self.parse_type());
+                    types.push(self.parse_type()?);
                     while self.current().kind == TokenKind::Pipe {
-                        self.expect(TokenKind::Pipe);
-                        types.push(self.parse_type());
+                        self.expect(TokenKind::Pipe)?;
+                        types.push(self.parse_type()?);
                     }
                 }
                 generic_parameters.push((match t {
                     Type::Unknown(id, span) => Type::GenericParameter(id, span),
-                    _ => panic!("Generic parameters must be unknown types")
+                    _ => return Err(ParseError::invalid("generic parameters must be new type names".to_string(), token))
                 }, generic_type, types));
                 if self.current().kind == TokenKind::Comma {
-                    self.expect(TokenKind::Comma);
+                    self.expect(TokenKind::Comma)?;
                 }
             }
-            self.expect(TokenKind::CloseBracket);
+            self.expect(TokenKind::CloseBracket)?;
         }
         for (generic_type, _, _) in generic_parameters.clone() {
             self.current_generic_parameters.push(generic_type);
         }
-        self.expect(TokenKind::OpenParenthesis);
+        self.expect(TokenKind::OpenParenthesis)?;
         let mut parameters: Vec<(String, Type)> = vec![];
         while self.current < self.tokens.len() && self.current().kind != TokenKind::CloseParenthesis {
-            let name: String = self.expect(TokenKind::Identifier).literal.unwrap();
-            self.expect(TokenKind::Colon);
-            let t: Type = self.parse_type();
+            let name: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
+            self.expect(TokenKind::Colon)?;
+            let t: Type = self.parse_type()?;
             parameters.push((name, t));
             if self.current().kind == TokenKind::Comma {
-                self.expect(TokenKind::Comma);
+                self.expect(TokenKind::Comma)?;
             }
         }
-        self.expect(TokenKind::CloseParenthesis);
-        self.expect(TokenKind::Arrow);
-        let return_type: Type = self.parse_type();
+        self.expect(TokenKind::CloseParenthesis)?;
+        self.expect(TokenKind::Arrow)?;
+        let return_type: Type = self.parse_type()?;
         for _ in 0..generic_parameters.len() {
             self.current_generic_parameters.pop();
         }
-        if self.current().kind == TokenKind::Equal {
-            self.expect(TokenKind::Equal);
-            if self.current().kind == TokenKind::Newline {
-                self.expect(TokenKind::Newline);
+        // The `=` can be left out before an indented block body.
+        let is_block: bool = self.current().kind == TokenKind::Newline && self.peek(1).kind == TokenKind::Indent;
+        if self.current().kind == TokenKind::Equal || is_block {
+            if self.current().kind == TokenKind::Equal {
+                self.expect(TokenKind::Equal)?;
             }
-            let expr: Expression = self.parse_expression();
-            self.expect(TokenKind::Newline);
+            let expr: Expression = if self.current().kind == TokenKind::Newline {
+                self.expect(TokenKind::Newline)?;
+                self.expect(TokenKind::Indent)?;
+                let expr: Expression = self.parse_block()?;
+                self.expect(TokenKind::Dedent)?;
+                expr
+            } else {
+                let expr: Expression = self.parse_expression()?;
+                self.expect_line_end()?;
+                expr
+            };
             if generic_parameters.len() > 0 {
                 self.procedures.insert(identifier.clone(), (parameters.clone(), return_type.clone(), expr.clone()));
                 self.generic_procedures.insert(identifier.clone(), generic_parameters.clone());
-                Statement::GenericProcedure(identifier, flags, generic_parameters, parameters, return_type, expr, name_span)
+                Ok(Statement::GenericProcedure(identifier, flags, generic_parameters, parameters, return_type, expr, name_span))
             } else {
                 self.procedures.insert(identifier.clone(), (parameters.clone(), return_type.clone(), expr.clone()));
-                Statement::Procedure(identifier, flags, parameters, return_type, expr, name_span)
+                Ok(Statement::Procedure(identifier, flags, parameters, return_type, expr, name_span))
             }
+        } else if self.in_trait && generic_parameters.is_empty() {
+            self.expect_line_end()?;
+            Ok(Statement::Signature(identifier, flags, parameters, return_type, name_span))
         } else {
-            panic!("Expected '=', block procedures are not yet supported");
+            Err(self.unexpected(vec![TokenKind::Equal]))
         }
     }
-    fn parse_trait(&mut self, flags: Vec<AccessFlag>) -> Statement {
-        self.expect(TokenKind::Trait);
-        panic!()
+    fn parse_trait(&mut self, flags: Vec<AccessFlag>) -> Result<Statement, ParseError> {
+        self.expect(TokenKind::Trait)?;
+        let name_span: Span = self.current().span;
+        let identifier: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
+        let mut generic_parameters: Vec<(Type, GenericType, Vec<Type>)> = vec![];
+        if self.current().kind == TokenKind::OpenBracket {
+            self.expect(TokenKind::OpenBracket)?;
+            while self.current < self.tokens.len() && self.current().kind != TokenKind::CloseBracket {
+                let token: Token = self.current();
+                let t: Type = self.parse_type()?;
+                let generic_type: GenericType = match self.current().kind {
+                    TokenKind::LessColon => {
+                        self.expect(TokenKind::LessColon)?;
+                        GenericType::Implements
+                    },
+                    TokenKind::GreaterColon => {
+                        self.expect(TokenKind::GreaterColon)?;
+                        GenericType::Extends
+                    },
+                    _ => GenericType::None
+                };
+                let mut types: Vec<Type> = vec![];
+                if generic_type != GenericType::None {
+                    types.push(self.parse_type()?);
+                    while self.current().kind == TokenKind::Pipe {
+                        self.expect(TokenKind::Pipe)?;
+                        types.push(self.parse_type()?);
+                    }
+                }
+                generic_parameters.push((match t {
+                    Type::Unknown(id, span) => Type::GenericParameter(id, span),
+                    _ => return Err(ParseError::invalid("generic parameters must be new type names".to_string(), token))
+                }, generic_type, types));
+                if self.current().kind == TokenKind::Comma {
+                    self.expect(TokenKind::Comma)?;
+                }
+            }
+            self.expect(TokenKind::CloseBracket)?;
+        }
+        for (generic_type, _, _) in generic_parameters.clone() {
+            self.current_generic_parameters.push(generic_type);
+        }
+        self.expect(TokenKind::Equal)?;
+        self.expect(TokenKind::Newline)?;
+        self.expect(TokenKind::Indent)?;
+        self.in_trait = true;
+        let mut members: Vec<Statement> = vec![];
+        while self.current().kind == TokenKind::Pipe || self.current().kind == TokenKind::DocComment {
+            let doc_span: Span = self.current().span;
+            let doc: String = self.parse_doc_comment()?;
+            self.expect(TokenKind::Pipe)?;
+            let access_flags: Vec<AccessFlag> = self.parse_access_flags()?;
+            if self.current().kind != TokenKind::Procedure {
+                return Err(self.unexpected(vec![TokenKind::Procedure]));
+            }
+            let procedure: Statement = self.parse_procedure(access_flags)?;
+            members.push(self.document(doc, procedure, doc_span));
+        }
+        self.in_trait = false;
+        self.expect(TokenKind::Dedent)?;
+        for _ in 0..generic_parameters.len() {
+            self.current_generic_parameters.pop();
+        }
+        if generic_parameters.len() > 0 {
+            Ok(Statement::GenericTrait(identifier, flags, generic_parameters, members, name_span))
+        } else {
+            Ok(Statement::Trait(identifier, flags, members, name_span))
+        }
     }
-    fn parse_module(&mut self) -> Statement {
-        self.expect(TokenKind::Module);
+    fn parse_module(&mut self) -> Result<Statement, ParseError> {
+        self.expect(TokenKind::Module)?;
         let span: Span = self.current().span;
-        let expr: Expression = self.parse_expression();
-        self.expect(TokenKind::Newline);
-        Statement::Module(expr, span)
+        let expr: Expression = self.parse_expression()?;
+        self.expect(TokenKind::Newline)?;
+        Ok(Statement::Module(expr, span))
     }
-    fn parse_import(&mut self) -> Statement {
-        self.expect(TokenKind::Import);
+    fn parse_import(&mut self) -> Result<Statement, ParseError> {
+        self.expect(TokenKind::Import)?;
         let span: Span = self.current().span;
-        let expr: Expression = self.parse_expression();
+        let expr: Expression = self.parse_expression()?;
         let mut exposed: Vec<String> = vec![];
         if self.current().kind == TokenKind::Exposing {
-            self.expect(TokenKind::Exposing);
+            self.expect(TokenKind::Exposing)?;
             if self.current().kind == TokenKind::OpenParenthesis {
-                self.expect(TokenKind::OpenParenthesis);
+                self.expect(TokenKind::OpenParenthesis)?;
                 while self.current().kind != TokenKind::CloseParenthesis {
                     if self.current().kind == TokenKind::Newline {
-                        self.expect(TokenKind::Newline);
+                        self.expect(TokenKind::Newline)?;
                     }
-                    exposed.push(self.expect(TokenKind::Identifier).literal.unwrap());
+                    exposed.push(self.expect(TokenKind::Identifier)?.literal.unwrap());
                     if self.current().kind == TokenKind::Comma {
-                        self.expect(TokenKind::Comma);
+                        self.expect(TokenKind::Comma)?;
                     }
                     if self.current().kind == TokenKind::Newline {
-                        self.expect(TokenKind::Newline);
+                        self.expect(TokenKind::Newline)?;
                     }
                 }
-                self.expect(TokenKind::CloseParenthesis);
+                self.expect(TokenKind::CloseParenthesis)?;
             } else {
-                exposed.push(self.expect(TokenKind::Identifier).literal.unwrap());
+                exposed.push(self.expect(TokenKind::Identifier)?.literal.unwrap());
             }
         }
-        self.expect(TokenKind::Newline);
+        self.expect(TokenKind::Newline)?;
         if exposed.len() > 0 {
-            Statement::ImportExposing(expr, exposed, span)
+            Ok(Statement::ImportExposing(expr, exposed, span))
         } else {
-            Statement::Import(expr, span)
+            Ok(Statement::Import(expr, span))
         }
     }
-    fn parse_of(&mut self) -> Statement {
+    fn parse_fixity(&mut self) -> Result<Statement, ParseError> {
         let span: Span = self.current().span;
-        self.expect(TokenKind::Of);
-        let t: Type = self.parse_type();
-        self.expect(TokenKind::Newline);
-        Statement::Of(t, span)
+        let associativity: Associativity = match self.current().kind {
+            TokenKind::Infixl => Associativity::Left,
+            TokenKind::Infixr => Associativity::Right,
+            _ => Associativity::None,
+        };
+        self.advance();
+        let token: Token = self.expect(TokenKind::IntegerLiteral)?;
+        let precedence: u8 = match literal::parse_integer(token.literal.as_ref().unwrap()) {
+            Ok((precedence, None)) if precedence <= 9 => precedence as u8,
+            _ => return Err(ParseError::invalid("operator precedence must be between 0 and 9".to_string(), token)),
+        };
+        let mut symbols: Vec<String> = vec![];
+        loop {
+            let symbol: String = self.parse_user_operator()?;
+            self.operators.insert(symbol.clone(), (precedence, associativity));
+            symbols.push(symbol);
+            if self.current().kind != TokenKind::Comma {
+                break;
+            }
+            self.expect(TokenKind::Comma)?;
+        }
+        self.expect(TokenKind::Newline)?;
+        Ok(Statement::Fixity(symbols, associativity, precedence, span))
+    }
+    // A user-defined operator's symbol, built-in operators can't be redeclared or redefined.
+    fn parse_user_operator(&mut self) -> Result<String, ParseError> {
+        let token: Token = self.current();
+        if token.kind != TokenKind::Operator {
+            if let Some(symbol) = self.operator_symbol() {
+                return Err(ParseError::invalid(format!("`{}` is a built-in operator and can't be redefined", symbol), token));
+            }
+            return Err(self.unexpected(vec![TokenKind::Operator]));
+        }
+        self.advance();
+        Ok(token.literal.unwrap())
+    }
+    // A procedure's name, or `(<>)` for a procedure defining an operator.
+    fn parse_procedure_name(&mut self) -> Result<String, ParseError> {
+        if self.current().kind != TokenKind::OpenParenthesis {
+            return Ok(self.expect(TokenKind::Identifier)?.literal.unwrap());
+        }
+        self.expect(TokenKind::OpenParenthesis)?;
+        let symbol: String = self.parse_user_operator()?;
+        self.expect(TokenKind::CloseParenthesis)?;
+        Ok(mangle_operator(&symbol))
+    }
+    fn parse_of(&mut self) -> Result<Statement, ParseError> {
+        let span: Span = self.current().span;
+        self.expect(TokenKind::Of)?;
+        let t: Type = self.parse_type()?;
+        self.expect(TokenKind::Newline)?;
+        Ok(Statement::Of(t, span))
     }
 
-    fn parse_expression(&mut self) -> Expression {
-        self.parse_addtitive()
+    // The lines of an indented body: `let` bindings, each on its own line, and then the result.
+    // The result may also follow an `in` after the last binding, Haskell style.
+    fn parse_block(&mut self) -> Result<Expression, ParseError> {
+        let span: Span = self.current().span;
+        let mut statements: Vec<Statement> = vec![];
+        while self.current().kind == TokenKind::Let {
+            statements.push(self.parse_let()?);
+            if self.current().kind == TokenKind::In {
+                break;
+            }
+            self.expect_line_end()?;
+        }
+        if !statements.is_empty() && self.current().kind == TokenKind::In {
+            self.expect(TokenKind::In)?;
+        }
+        let result: Expression = self.parse_expression()?;
+        self.expect_line_end()?;
+        if statements.is_empty() {
+            return Ok(result);
+        }
+        Ok(Expression::Block(statements, Box::new(result), span))
     }
-    fn parse_addtitive(&mut self) -> Expression {
-        let mut expr: Expression = self.parse_multiplicative();
-        while self.current().kind == TokenKind::Plus || self.current().kind == TokenKind::Minus {
-            let span: Span = self.current().span;
-            let op: TokenKind = self.current().kind;
+    // `let name = value` or `let name: Type = value`, or `let (a, b) = value` to destructure.
+    fn parse_let(&mut self) -> Result<Statement, ParseError> {
+        let span: Span = self.current().span;
+        self.expect(TokenKind::Let)?;
+        let mut pattern: Option<Pattern> = None;
+        let mut name: String = String::new();
+        if self.current().kind == TokenKind::OpenParenthesis {
+            pattern = Some(self.parse_single_pattern()?);
+        } else {
+            name = self.expect(TokenKind::Identifier)?.literal.unwrap();
+        }
+        let mut t: Option<Type> = None;
+        if self.current().kind == TokenKind::Colon {
+            self.expect(TokenKind::Colon)?;
+            t = Some(self.parse_type()?);
+        }
+        self.expect(TokenKind::Equal)?;
+        let value: Expression = self.parse_expression()?;
+        match pattern {
+            Some(pattern) => Ok(Statement::Destructure(pattern, t, value, span)),
+            None => Ok(Statement::Let(name, t, value, span)),
+        }
+    }
+    fn parse_expression(&mut self) -> Result<Expression, ParseError> {
+        self.parse_binary(0)
+    }
+    // Precedence climbing over the operator table: only operators binding at least as tightly
+    // as `min_precedence` are consumed here, looser ones are left for the callers further up.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expression, ParseError> {
+        let mut expr: Expression = self.parse_unary()?;
+        while let Some(symbol) = self.operator_symbol() {
+            let (precedence, associativity): (u8, Associativity) = self.fixity(&symbol);
+            if precedence < min_precedence {
+                break;
+            }
+            let token: Token = self.current();
             self.advance();
-            let right: Expression = self.parse_expression();
-            expr = Expression::Binary(Box::new(expr), Box::new(right), op, span);
+            let right: Expression = match associativity {
+                Associativity::Right => self.parse_binary(precedence)?,
+                Associativity::Left | Associativity::None => self.parse_binary(precedence + 1)?,
+            };
+            expr = match token.kind {
+                TokenKind::Operator => {
+                    let callee: Expression = Expression::Variable(mangle_operator(&symbol), token.span.clone());
+                    Expression::Call(Box::new(callee), vec![], vec![expr, right], token.span)
+                }
+                kind => Expression::Binary(Box::new(expr), Box::new(right), kind, token.span),
+            };
+            if associativity == Associativity::None {
+                if let Some(next) = self.operator_symbol() {
+                    if self.fixity(&next) == (precedence, Associativity::None) {
+                        let message: String = format!("`{}` and `{}` are non-associative and can't be chained without parentheses", symbol, next);
+                        return Err(ParseError::invalid(message, self.current()));
+                    }
+                }
+            }
         }
-        expr
+        Ok(expr)
     }
-    fn parse_multiplicative(&mut self) -> Expression {
-        let mut expr: Expression = self.parse_call();
-        while self.current().kind == TokenKind::Asterisk || self.current().kind == TokenKind::Slash {
+    fn parse_unary(&mut self) -> Result<Expression, ParseError> {
+        if self.current().kind == TokenKind::Bang || self.current().kind == TokenKind::Minus {
             let span: Span = self.current().span;
             let op: TokenKind = self.current().kind;
             self.advance();
-            let right: Expression = self.parse_expression();
-            expr = Expression::Binary(Box::new(expr), Box::new(right), op, span);
+            // The smallest integers, like `-2147483648`, are only in range once negated.
+            let postfix: bool = matches!(self.peek(1).kind, TokenKind::OpenParenthesis | TokenKind::OpenBracket | TokenKind::Dot);
+            if op == TokenKind::Minus && self.current().kind == TokenKind::IntegerLiteral && !postfix {
+                let literal: Expression = self.parse_integer(true)?;
+                return Ok(Expression::Unary(Box::new(literal), op, span));
+            }
+            let operand: Expression = self.parse_unary()?;
+            return Ok(Expression::Unary(Box::new(operand), op, span));
         }
-        expr
+        self.parse_call()
     }
-    fn parse_call(&mut self) -> Expression {
+    // Calls, method calls and member accesses, which all chain left to right.
+    fn parse_call(&mut self) -> Result<Expression, ParseError> {
         let span: Span = self.current().span;
-        let mut expr: Expression = self.parse_member();
-        while self.current().kind == TokenKind::OpenParenthesis {
-            self.expect(TokenKind::OpenParenthesis);
-            let mut args: Vec<Expression> = vec![];
-            while self.current().kind != TokenKind::CloseParenthesis {
-                args.push(self.parse_expression());
-                if self.current().kind == TokenKind::Comma {
-                    self.expect(TokenKind::Comma);
+        let mut expr: Expression = self.parse_primary()?;
+        loop {
+            match self.current().kind {
+                TokenKind::OpenParenthesis => {
+                    if let Some(name) = self.struct_name(&expr) {
+                        let fields: Vec<(Option<String>, Expression)> = self.parse_field_arguments()?;
+                        expr = Expression::Construct(name, vec![], fields, span.clone());
+                        continue;
+                    }
+                    let args: Vec<Expression> = self.parse_arguments()?;
+                    expr = Expression::Call(Box::new(expr), vec![], args, span.clone());
                 }
+                // `f[Int](x)` when `f` takes type arguments, `fs[i](x)` calls an element otherwise.
+                TokenKind::OpenBracket => {
+                    let types: Option<Vec<Type>> = match &expr {
+                        Expression::Variable(name, _) if self.generic_names.contains(name) => self.parse_type_arguments(),
+                        _ => None,
+                    };
+                    if let Some(types) = types {
+                        if let Some(name) = self.struct_name(&expr) {
+                            let fields: Vec<(Option<String>, Expression)> = self.parse_field_arguments()?;
+                            expr = Expression::Construct(name, types, fields, span.clone());
+                            continue;
+                        }
+                        let args: Vec<Expression> = self.parse_arguments()?;
+                        expr = Expression::Call(Box::new(expr), types, args, span.clone());
+                        continue;
+                    }
+                    let index_span: Span = self.current().span;
+                    self.expect(TokenKind::OpenBracket)?;
+                    let mut start: Option<Box<Expression>> = None;
+                    if self.current().kind != TokenKind::DotDot {
+                        start = Some(Box::new(self.parse_expression()?));
+                    }
+                    if self.current().kind == TokenKind::DotDot {
+                        self.expect(TokenKind::DotDot)?;
+                        let mut end: Option<Box<Expression>> = None;
+                        if self.current().kind != TokenKind::CloseBracket {
+                            end = Some(Box::new(self.parse_expression()?));
+                        }
+                        self.expect(TokenKind::CloseBracket)?;
+                        expr = Expression::Slice(Box::new(expr), start, end, index_span);
+                    } else {
+                        self.expect(TokenKind::CloseBracket)?;
+                        expr = Expression::Index(Box::new(expr), start.unwrap(), index_span);
+                    }
+                }
+                TokenKind::Dot => {
+                    self.expect(TokenKind::Dot)?;
+                    let member_span: Span = self.current().span;
+                    let name: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
+                    let types: Vec<Type> = match self.current().kind {
+                        TokenKind::OpenBracket => self.parse_type_arguments().unwrap_or_default(),
+                        _ => vec![],
+                    };
+                    expr = if self.current().kind == TokenKind::OpenParenthesis {
+                        let args: Vec<Expression> = self.parse_arguments()?;
+                        Expression::MethodCall(Box::new(expr), name, types, args, member_span)
+                    } else {
+                        Expression::Member(Box::new(expr), name, member_span)
+                    };
+                }
+                // `match value with` is followed by the cases, an update by braces.
+                TokenKind::With if self.peek(1).kind == TokenKind::OpenBrace => {
+                    let with_span: Span = self.expect(TokenKind::With)?.span;
+                    self.expect(TokenKind::OpenBrace)?;
+                    let mut fields: Vec<(String, Expression)> = vec![];
+                    while self.current().kind != TokenKind::CloseBrace {
+                        let field: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
+                        self.expect(TokenKind::Equal)?;
+                        fields.push((field, self.parse_expression()?));
+                        if self.current().kind != TokenKind::CloseBrace {
+                            self.expect(TokenKind::Comma)?;
+                        }
+                    }
+                    self.expect(TokenKind::CloseBrace)?;
+                    expr = Expression::With(Box::new(expr), fields, with_span);
+                }
+                _ => break,
             }
-            self.expect(TokenKind::CloseParenthesis);
-            expr = Expression::Call(match expr {
-                Expression::Variable(name, _) => name,
-                _ => panic!("unexpected expression: {:?}", expr)
-            }, args, span.clone());
         }
-        expr
+        Ok(expr)
     }
-    fn parse_member(&mut self) -> Expression {
-        let mut expr: Expression = self.parse_primary();
-        while self.current().kind == TokenKind::Dot {
-            self.expect(TokenKind::Dot);
-            let span: Span = self.current().span;
-            expr = Expression::Member(Box::new(expr), Box::new(self.parse_expression()), span);
+    fn parse_arguments(&mut self) -> Result<Vec<Expression>, ParseError> {
+        self.expect(TokenKind::OpenParenthesis)?;
+        let mut args: Vec<Expression> = vec![];
+        while self.current().kind != TokenKind::CloseParenthesis {
+            args.push(self.parse_expression()?);
+            if self.current().kind == TokenKind::Comma {
+                self.expect(TokenKind::Comma)?;
+            }
         }
-        expr
+        self.expect(TokenKind::CloseParenthesis)?;
+        Ok(args)
     }
-    fn parse_primary(&mut self) -> Expression {
+    // The name of the data struct `callee` refers to, when it does.
+    fn struct_name(&self, callee: &Expression) -> Option<String> {
+        match callee {
+            Expression::Variable(name, _) if self.struct_names.contains(name) => Some(name.clone()),
+            _ => None,
+        }
+    }
+    // `(a = 1, 2)`, like call arguments but each may name the field it is for.
+    fn parse_field_arguments(&mut self) -> Result<Vec<(Option<String>, Expression)>, ParseError> {
+        self.expect(TokenKind::OpenParenthesis)?;
+        let mut fields: Vec<(Option<String>, Expression)> = vec![];
+        while self.current().kind != TokenKind::CloseParenthesis {
+            let mut field: Option<String> = None;
+            if self.current().kind == TokenKind::Identifier && self.peek(1).kind == TokenKind::Equal {
+                field = self.expect(TokenKind::Identifier)?.literal;
+                self.expect(TokenKind::Equal)?;
+            }
+            fields.push((field, self.parse_expression()?));
+            if self.current().kind == TokenKind::Comma {
+                self.expect(TokenKind::Comma)?;
+            }
+        }
+        self.expect(TokenKind::CloseParenthesis)?;
+        Ok(fields)
+    }
+    // `[Int, String]` right before a call's arguments. Anything else in brackets isn't type
+    // arguments, so this backtracks and returns `None` to let it be parsed some other way.
+    fn parse_type_arguments(&mut self) -> Option<Vec<Type>> {
+        let (current, depth): (usize, usize) = (self.current, self.depth);
+        let mut parse = || -> Result<Vec<Type>, ParseError> {
+            self.expect(TokenKind::OpenBracket)?;
+            let mut types: Vec<Type> = vec![self.parse_type()?];
+            while self.current().kind == TokenKind::Comma {
+                self.expect(TokenKind::Comma)?;
+                types.push(self.parse_type()?);
+            }
+            self.expect(TokenKind::CloseBracket)?;
+            if self.current().kind != TokenKind::OpenParenthesis {
+                return Err(self.unexpected(vec![TokenKind::OpenParenthesis]));
+            }
+            Ok(types)
+        };
+        match parse() {
+            Ok(types) => Some(types),
+            Err(_) => {
+                self.current = current;
+                self.depth = depth;
+                None
+            }
+        }
+    }
+    fn parse_integer(&mut self, negated: bool) -> Result<Expression, ParseError> {
+        let token: Token = self.expect(TokenKind::IntegerLiteral)?;
+        let literal: String = token.literal.clone().unwrap();
+        // The lexer has already range-checked the literal, so decoding it can't fail here.
+        let (integer, suffix) = literal::parse_integer(&literal).unwrap();
+        if integer as u128 > literal::integer_max(suffix) && !negated {
+            let name: &str = suffix.map_or("Int", |suffix| suffix.name());
+            return Err(ParseError::invalid(format!("integer literal '{}' is out of range for {}", literal, name), token));
+        }
+        Ok(Expression::Integer(integer, suffix, token.span))
+    }
+    fn parse_primary(&mut self) -> Result<Expression, ParseError> {
         let span: Span = self.current().span;
         match self.current().kind {
             TokenKind::Identifier => {
-                let identifier: String = self.expect(TokenKind::Identifier).literal.unwrap();
-                Expression::Variable(identifier, span)
+                let identifier: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
+                Ok(Expression::Variable(identifier, span))
             }
             TokenKind::StringLiteral => {
-                let string: String = self.expect(TokenKind::StringLiteral).literal.unwrap();
-                Expression::String(string, span)
+                let string: String = self.expect(TokenKind::StringLiteral)?.literal.unwrap();
+                Ok(Expression::String(string, span))
+            }
+            TokenKind::True => {
+                self.expect(TokenKind::True)?;
+                Ok(Expression::Bool(true, span))
             }
-            TokenKind::IntegerLiteral => {
-                let integer: i64 = self.expect(TokenKind::IntegerLiteral).literal.unwrap().parse::<i64>().unwrap();
-                Expression::Integer(integer, span)
+            TokenKind::False => {
+                self.expect(TokenKind::False)?;
+                Ok(Expression::Bool(false, span))
+            }
+            TokenKind::OpenParenthesis => {
+                self.expect(TokenKind::OpenParenthesis)?;
+                let expression: Expression = self.parse_expression()?;
+                if self.current().kind != TokenKind::Comma {
+                    self.expect(TokenKind::CloseParenthesis)?;
+                    return Ok(expression);
+                }
+                let mut items: Vec<Expression> = vec![expression];
+                while self.current().kind == TokenKind::Comma {
+                    self.expect(TokenKind::Comma)?;
+                    items.push(self.parse_expression()?);
+                }
+                self.expect(TokenKind::CloseParenthesis)?;
+                Ok(Expression::Tuple(items, span))
+            }
+            TokenKind::StringStart => {
+                let mut parts: Vec<StringPart> = vec![];
+                let text: String = self.expect(TokenKind::StringStart)?.literal.unwrap();
+                if !text.is_empty() {
+                    parts.push(StringPart::Text(text));
+                }
+                loop {
+                    parts.push(StringPart::Expression(self.parse_expression()?));
+                    let end: bool = self.current().kind == TokenKind::StringEnd;
+                    let text: String = self.expect(if end { TokenKind::StringEnd } else { TokenKind::StringMiddle })?.literal.unwrap();
+                    if !text.is_empty() {
+                        parts.push(StringPart::Text(text));
+                    }
+                    if end {
+                        break;
+                    }
+                }
+                Ok(Expression::Interpolated(parts, span))
+            }
+            TokenKind::CharLiteral => {
+                let char: char = self.expect(TokenKind::CharLiteral)?.literal.unwrap().chars().next().unwrap();
+                Ok(Expression::Char(char, span))
+            }
+            TokenKind::IntegerLiteral => self.parse_integer(false),
+            TokenKind::FloatLiteral => {
+                let float: f64 = literal::parse_float(&self.expect(TokenKind::FloatLiteral)?.literal.unwrap()).unwrap();
+                Ok(Expression::Float(float, span))
+            }
+            TokenKind::If => {
+                self.expect(TokenKind::If)?;
+                let condition: Expression = self.parse_expression()?;
+                self.skip_newline_before(TokenKind::Then);
+                self.expect(TokenKind::Then)?;
+                let then_branch: Expression = self.parse_expression()?;
+                self.skip_newline_before(TokenKind::Else);
+                self.expect(TokenKind::Else)?;
+                let else_branch: Expression = self.parse_expression()?;
+                Ok(Expression::If(Box::new(condition), Box::new(then_branch), Box::new(else_branch), span))
             }
             TokenKind::Match => {
-                self.expect(TokenKind::Match);
-                let expr: Expression = self.parse_expression();
-                self.expect(TokenKind::With);
-                self.expect(TokenKind::Newline);
+                self.expect(TokenKind::Match)?;
+                let expr: Expression = self.parse_expression()?;
+                self.expect(TokenKind::With)?;
+                self.expect(TokenKind::Newline)?;
+                self.expect(TokenKind::Indent)?;
                 let mut cases: Vec<MatchCase> = vec![];
                 let mut else_case: Option<MatchCase> = None;
                 while self.current().kind == TokenKind::Pipe {
-                    self.expect(TokenKind::Pipe);
+                    self.expect(TokenKind::Pipe)?;
+                    let case_span: Span = self.current().span;
                     if self.current().kind == TokenKind::Else {
-                        self.expect(TokenKind::Else);
-                        self.expect(TokenKind::Arrow);
-                        let mut body: Vec<Statement> = vec![];
-                        if self.current().kind == TokenKind::OpenBrace {
-                            self.expect(TokenKind::OpenBrace);
-                            while self.current().kind != TokenKind::CloseBrace {
-                                body.push(self.parse_statement());
-                            }
-                            self.expect(TokenKind::CloseBrace);
-                        } else {
-                            body.push(Statement::Expression(self.parse_expression(), span.clone()));
-                            if self.current().kind == TokenKind::Newline {
-                                self.expect(TokenKind::Newline);
-                            }
-                        }
+                        self.expect(TokenKind::Else)?;
+                        self.expect(TokenKind::Arrow)?;
+                        let body: Vec<Statement> = self.parse_case_body(span.clone())?;
                         else_case = Some(MatchCase {
-                            condition: vec![],
+                            pattern: Pattern::Wildcard(case_span.clone()),
+                            guard: None,
                             body,
-                            span: self.current().span
+                            span: case_span
                         });
                         break;
                     }
-                    let mut condition: Vec<Expression> = vec![];
-                    condition.push(self.parse_expression());
-                    while self.current().kind == TokenKind::Pipe {
-                        self.expect(TokenKind::Pipe);
-                        condition.push(self.parse_expression());
-                    }
-                    self.expect(TokenKind::Arrow);
-                    let mut body: Vec<Statement> = vec![];
-                    if self.current().kind == TokenKind::OpenBrace {
-                        self.expect(TokenKind::OpenBrace);
-                        while self.current().kind != TokenKind::CloseBrace {
-                            body.push(self.parse_statement());
-                        }
-                        self.expect(TokenKind::CloseBrace);
-                    } else {
-                        body.push(Statement::Expression(self.parse_expression(), span.clone()));
-                        if self.current().kind == TokenKind::Newline {
-                            self.expect(TokenKind::Newline);
-                        }
+                    let pattern: Pattern = self.parse_pattern()?;
+                    let mut guard: Option<Box<Expression>> = None;
+                    if self.current().kind == TokenKind::If {
+                        self.expect(TokenKind::If)?;
+                        guard = Some(Box::new(self.parse_expression()?));
                     }
+                    self.expect(TokenKind::Arrow)?;
+                    let body: Vec<Statement> = self.parse_case_body(span.clone())?;
                     cases.push(MatchCase {
-                        condition,
+                        pattern,
+                        guard,
                         body,
-                        span: self.current().span
+                        span: case_span
                     });
                 }
-                Expression::Match(Box::new(expr), cases, else_case, span)
+                self.expect(TokenKind::Dedent)?;
+                Ok(Expression::Match(Box::new(expr), cases, else_case, span))
+            }
+            TokenKind::Backslash => {
+                self.expect(TokenKind::Backslash)?;
+                let mut parameters: Vec<(String, Option<Type>)> = vec![];
+                loop {
+                    let name: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
+                    let mut t: Option<Type> = None;
+                    if self.current().kind == TokenKind::Colon {
+                        self.expect(TokenKind::Colon)?;
+                        t = Some(self.parse_type()?);
+                    }
+                    parameters.push((name, t));
+                    if self.current().kind != TokenKind::Comma {
+                        break;
+                    }
+                    self.expect(TokenKind::Comma)?;
+                }
+                self.expect(TokenKind::Arrow)?;
+                // The body extends as far right as possible, so `\x -> x + 1` is one lambda.
+                let body: Expression = self.parse_expression()?;
+                Ok(Expression::Lambda(parameters, Box::new(body), span))
+            }
+            TokenKind::Let => {
+                let binding: Statement = self.parse_let()?;
+                self.expect(TokenKind::In)?;
+                let body: Expression = self.parse_expression()?;
+                Ok(Expression::Block(vec![binding], Box::new(body), span))
             }
             TokenKind::Unsafe => {
                 let span: Span = self.current().span;
-                self.expect(TokenKind::Unsafe);
-                let expression = self.parse_expression();
-                Expression::Unsafe(Box::new(expression), span)
+                self.expect(TokenKind::Unsafe)?;
+                let expression = self.parse_expression()?;
+                Ok(Expression::Unsafe(Box::new(expression), span))
             }
             TokenKind::Cpp => {
                 let span: Span = self.current().span;
-                self.expect(TokenKind::Cpp);
-                let cpp: String = self.expect(TokenKind::StringLiteral).literal.unwrap(); 
-                Expression::Cpp(cpp, span)
+                self.expect(TokenKind::Cpp)?;
+                let cpp: String = self.expect(TokenKind::StringLiteral)?.literal.unwrap(); 
+                Ok(Expression::Cpp(cpp, span))
             }
             TokenKind::OpenBracket => {
                 let span: Span = self.current().span;
-                self.expect(TokenKind::OpenBracket);
+                self.expect(TokenKind::OpenBracket)?;
                 let mut items: Vec<Expression> = vec![];
                 while self.current().kind != TokenKind::CloseBracket {
-                    items.push(self.parse_expression());
+                    items.push(self.parse_expression()?);
                     if self.current().kind == TokenKind::Comma {
-                        self.expect(TokenKind::Comma);
+                        self.expect(TokenKind::Comma)?;
                     }
                 }
-                self.expect(TokenKind::CloseBracket);
-                Expression::List(items, span)
+                self.expect(TokenKind::CloseBracket)?;
+                Ok(Expression::List(items, span))
             }
             TokenKind::OpenBrace => {
                 let span: Span = self.current().span;
-                self.expect(TokenKind::OpenBrace);
+                self.expect(TokenKind::OpenBrace)?;
                 let mut map: Vec<(Expression, Expression)> = vec![];
                 while self.current().kind != TokenKind::CloseBrace {
-                    let key: Expression = self.parse_expression();
-                    self.expect(TokenKind::Colon);
-                    let value: Expression = self.parse_expression();
+                    let key: Expression = self.parse_expression()?;
+                    self.expect(TokenKind::Colon)?;
+                    let value: Expression = self.parse_expression()?;
                     map.push((key, value));
                     if self.current().kind == TokenKind::Comma {
-                        self.expect(TokenKind::Comma);
+                        self.expect(TokenKind::Comma)?;
                     }
                 }
-                self.expect(TokenKind::CloseBrace);
-                Expression::Map(map, span)
+                self.expect(TokenKind::CloseBrace)?;
+                Ok(Expression::Map(map, span))
             }
-            _ => panic!("unexpected token: {:?}", self.current().kind)
+            kind => Err(ParseError::invalid(format!("expected an expression, found {}", kind.describe()), self.current()))
         }
     }
 
-    fn parse_access_flags(&mut self) -> Vec<AccessFlag> {
+    // Consecutive `///` lines, or an empty string if there are none.
+    fn parse_doc_comment(&mut self) -> Result<String, ParseError> {
+        let mut lines: Vec<String> = vec![];
+        while self.current().kind == TokenKind::DocComment {
+            lines.push(self.expect(TokenKind::DocComment)?.literal.unwrap());
+            self.expect(TokenKind::Newline)?;
+        }
+        Ok(lines.join("\n"))
+    }
+    // Attaches `doc` to declarations; doc comments on anything else are dropped.
+    fn document(&mut self, doc: String, statement: Statement, span: Span) -> Statement {
+        if doc.is_empty() {
+            return statement;
+        }
+        match statement {
+            Statement::DataEnum(..) | Statement::DataStruct(..) | Statement::Alias(..) |
+            Statement::Object(..) | Statement::Procedure(..) | Statement::Const(..) | Statement::Trait(..) | Statement::Signature(..) |
+            Statement::GenericDataEnum(..) | Statement::GenericDataStruct(..) | Statement::GenericAlias(..) |
+            Statement::GenericObject(..) | Statement::GenericProcedure(..) | Statement::GenericTrait(..) => {
+                Statement::Documented(doc, Box::new(statement), span)
+            }
+            _ => statement,
+        }
+    }
+    fn parse_access_flags(&mut self) -> Result<Vec<AccessFlag>, ParseError> {
         let mut flags: Vec<AccessFlag> = vec![];
         while self.current().kind == TokenKind::Public || 
                 self.current().kind == TokenKind::Private || 
@@ -756,39 +1445,39 @@ impl Parser {
                 TokenKind::Internal => AccessFlag::Internal,
                 TokenKind::Virtual => AccessFlag::Virtual,
                 TokenKind::Override => AccessFlag::Override,
-                _ => panic!("unexpected token: {:?}", self.current().kind)
+                _ => unreachable!()
             };
             if flags.contains(&flag) {
-                panic!("duplicate access flag: {:?}", flag);
+                return Err(ParseError::invalid(format!("duplicate access flag {}", self.current().kind.describe()), self.current()));
             }
             flags.push(flag);
             self.advance();
         }
-        flags
+        Ok(flags)
     }
-    fn parse_type(&mut self) -> Type {
+    fn parse_type(&mut self) -> Result<Type, ParseError> {
         let span: Span = self.current().span;
         let base_t: Type = match self.current().kind {
             TokenKind::Identifier => {
                 let current: String = self.current().literal.unwrap();
                 if self.data_enums.contains_key(&current) {
-                    let identifier: String = self.expect(TokenKind::Identifier).literal.unwrap();
+                    let identifier: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
                     Type::DataEnum(identifier, span.clone())
                 } else if self.data_structs.contains_key(&current) {
-                    let identifier: String = self.expect(TokenKind::Identifier).literal.unwrap();
+                    let identifier: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
                     Type::DataStruct(identifier, span.clone())
                 } else if self.aliases.contains_key(&current) {
-                    let identifier: String = self.expect(TokenKind::Identifier).literal.unwrap();
+                    let identifier: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
                     Type::Alias(identifier, span.clone())
                 } else if self.objects.contains_key(&current) {
-                    let identifier: String = self.expect(TokenKind::Identifier).literal.unwrap();
+                    let identifier: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
                     Type::Object(identifier, span.clone())
                 } else {
                     for t in self.current_generic_parameters.clone() {
                         if let Type::GenericParameter(name, _) = t.clone() {
                             if name == current {
                                 self.advance();
-                                return Type::Generic(name, span.clone());
+                                return Ok(Type::Generic(name, span.clone()));
                             }
                         }
                     }
@@ -797,98 +1486,418 @@ impl Parser {
                 }
             }
             TokenKind::Unit => {
-                self.expect(TokenKind::Unit);
+                self.expect(TokenKind::Unit)?;
                 Type::Unit(span.clone())
             }
             TokenKind::Int => {
-                self.expect(TokenKind::Int);
+                self.expect(TokenKind::Int)?;
                 Type::Int(span.clone())
             }
+            TokenKind::Float => {
+                self.expect(TokenKind::Float)?;
+                Type::Float(span.clone())
+            }
             TokenKind::Char => {
-                self.expect(TokenKind::Char);
+                self.expect(TokenKind::Char)?;
                 Type::Char(span.clone())
             }
             TokenKind::Bool => {
-                self.expect(TokenKind::Bool);
+                self.expect(TokenKind::Bool)?;
                 Type::Bool(span.clone())
             }
             TokenKind::OpenBracket => {
-                self.expect(TokenKind::OpenBracket);
-                let t: Type = self.parse_type();
-                self.expect(TokenKind::CloseBracket);
+                self.expect(TokenKind::OpenBracket)?;
+                let t: Type = self.parse_type()?;
+                self.expect(TokenKind::CloseBracket)?;
                 Type::Array(Box::new(t), span.clone())
             }
             TokenKind::OpenParenthesis => {
-                self.expect(TokenKind::OpenParenthesis);
+                self.expect(TokenKind::OpenParenthesis)?;
                 let mut types: Vec<Type> = vec![];
                 while self.current < self.tokens.len() && self.current().kind != TokenKind::CloseParenthesis {
-                    types.push(self.parse_type());
+                    types.push(self.parse_type()?);
                     if self.current().kind == TokenKind::Comma {
-                        self.expect(TokenKind::Comma);
+                        self.expect(TokenKind::Comma)?;
                     }
                 }
-                self.expect(TokenKind::CloseParenthesis);
-                self.expect(TokenKind::Arrow);
-                let t: Type = self.parse_type();
-                Type::Function(types, Box::new(t), span.clone())
+                self.expect(TokenKind::CloseParenthesis)?;
+                if self.current().kind == TokenKind::Arrow {
+                    self.expect(TokenKind::Arrow)?;
+                    let t: Type = self.parse_type()?;
+                    Type::Function(types, Box::new(t), span.clone())
+                } else if types.len() >= 2 {
+                    Type::Tuple(types, span.clone())
+                } else {
+                    return Err(self.unexpected(vec![TokenKind::Arrow]));
+                }
             }
-            _ => panic!("unexpected type: {:?}", self.current().kind)
+            kind => return Err(ParseError::invalid(format!("expected a type, found {}", kind.describe()), self.current()))
         };
         if self.current().kind == TokenKind::OpenBracket {
-            self.expect(TokenKind::OpenBracket);
+            self.expect(TokenKind::OpenBracket)?;
             let mut inner_types: Vec<Type> = vec![];
             while self.current().kind != TokenKind::CloseBracket {
-                inner_types.push(self.parse_type());
+                inner_types.push(self.parse_type()?);
                 if self.current().kind == TokenKind::Comma {
-                    self.expect(TokenKind::Comma);
+                    self.expect(TokenKind::Comma)?;
                 }
             }
-            self.expect(TokenKind::CloseBracket);
-            return Type::GenericType(Box::new(base_t), inner_types, span.clone());
+            self.expect(TokenKind::CloseBracket)?;
+            return Ok(Type::GenericType(Box::new(base_t), inner_types, span.clone()));
         }
         if self.current().kind == TokenKind::QuestionMark {
-            self.expect(TokenKind::QuestionMark);
-            return Type::Optional(Box::new(base_t), span.clone());
+            self.expect(TokenKind::QuestionMark)?;
+            return Ok(Type::Optional(Box::new(base_t), span.clone()));
         }
-        base_t
+        Ok(base_t)
     }
-    fn parse_enum_variant(&mut self) -> EnumVarient {
+    fn parse_enum_variant(&mut self) -> Result<EnumVarient, ParseError> {
         let span: Span = self.current().span;
-        let identifier: String = self.expect(TokenKind::Identifier).literal.unwrap();
+        let identifier: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
         if self.current().kind == TokenKind::OpenParenthesis {
-            self.expect(TokenKind::OpenParenthesis);
+            self.expect(TokenKind::OpenParenthesis)?;
             let mut types: Vec<Type> = vec![];
             while self.current < self.tokens.len() && self.current().kind != TokenKind::CloseParenthesis {
-                types.push(self.parse_type());
+                types.push(self.parse_type()?);
                 if self.current().kind == TokenKind::Comma {
-                    self.expect(TokenKind::Comma);
+                    self.expect(TokenKind::Comma)?;
                 }
             }
-            self.expect(TokenKind::CloseParenthesis);
-            EnumVarient::Tuple(identifier, types, span)
+            self.expect(TokenKind::CloseParenthesis)?;
+            Ok(EnumVarient::Tuple(identifier, types, span))
         } else {
-            EnumVarient::Unit(identifier, span)
+            Ok(EnumVarient::Unit(identifier, span))
         }
     }
 
-    fn expect(&mut self, kind: TokenKind) -> Token {
+    // The symbol of the current token if it is a binary operator.
+    fn operator_symbol(&mut self) -> Option<String> {
+        let token: Token = self.current();
+        let symbol: &str = match token.kind {
+            TokenKind::Operator => return token.literal,
+            TokenKind::PipePipe => "||",
+            TokenKind::AmpersandAmpersand => "&&",
+            TokenKind::EqualEqual => "==",
+            TokenKind::BangEqual => "!=",
+            TokenKind::Less => "<",
+            TokenKind::LessEqual => "<=",
+            TokenKind::Greater => ">",
+            TokenKind::GreaterEqual => ">=",
+            TokenKind::Plus => "+",
+            TokenKind::Minus => "-",
+            TokenKind::Asterisk => "*",
+            TokenKind::Slash => "/",
+            TokenKind::Percent => "%",
+            _ => return None,
+        };
+        Some(symbol.to_string())
+    }
+    // Operators without an `infixl`/`infixr`/`infix` declaration default to `infixl 9`, like in Haskell.
+    fn fixity(&self, symbol: &str) -> (u8, Associativity) {
+        self.operators.get(symbol).copied().unwrap_or((9, Associativity::Left))
+    }
+
+    fn parse_case_body(&mut self, span: Span) -> Result<Vec<Statement>, ParseError> {
+        let mut body: Vec<Statement> = vec![];
+        if self.current().kind == TokenKind::OpenBrace {
+            self.expect(TokenKind::OpenBrace)?;
+            while self.current().kind != TokenKind::CloseBrace {
+                body.push(self.parse_statement()?);
+            }
+            self.expect(TokenKind::CloseBrace)?;
+        } else if self.current().kind == TokenKind::Newline && self.peek(1).kind == TokenKind::Indent {
+            // An indented block on the lines after the `->`, like a procedure body.
+            self.expect(TokenKind::Newline)?;
+            self.expect(TokenKind::Indent)?;
+            body.push(Statement::Expression(self.parse_block()?, span));
+            self.expect(TokenKind::Dedent)?;
+            return Ok(body);
+        } else {
+            body.push(Statement::Expression(self.parse_expression()?, span));
+        }
+        self.expect_line_end()?;
+        Ok(body)
+    }
+    // Alternatives are separated by `|` and the pattern ends at the first token after one that
+    // isn't a `|`, the `if` or `->` of its case. Cases start with `|` on a new line of their own.
+    fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
+        let span: Span = self.current().span;
+        let mut alternatives: Vec<Pattern> = vec![self.parse_single_pattern()?];
+        while self.current().kind == TokenKind::Pipe {
+            self.expect(TokenKind::Pipe)?;
+            alternatives.push(self.parse_single_pattern()?);
+        }
+        if alternatives.len() == 1 {
+            return Ok(alternatives.pop().unwrap());
+        }
+        Ok(Pattern::Or(alternatives, span))
+    }
+    fn parse_single_pattern(&mut self) -> Result<Pattern, ParseError> {
+        let span: Span = self.current().span;
+        match self.current().kind {
+            TokenKind::IntegerLiteral | TokenKind::FloatLiteral | TokenKind::CharLiteral |
+            TokenKind::StringLiteral | TokenKind::True | TokenKind::False => {
+                Ok(Pattern::Literal(Box::new(self.parse_primary()?), span))
+            }
+            TokenKind::Minus => {
+                self.expect(TokenKind::Minus)?;
+                if !matches!(self.current().kind, TokenKind::IntegerLiteral | TokenKind::FloatLiteral) {
+                    return Err(self.unexpected(vec![TokenKind::IntegerLiteral, TokenKind::FloatLiteral]));
+                }
+                let literal: Expression = match self.current().kind {
+                    TokenKind::IntegerLiteral => self.parse_integer(true)?,
+                    _ => self.parse_primary()?,
+                };
+                Ok(Pattern::Literal(Box::new(Expression::Unary(Box::new(literal), TokenKind::Minus, span.clone())), span))
+            }
+            TokenKind::OpenParenthesis => {
+                self.expect(TokenKind::OpenParenthesis)?;
+                let pattern: Pattern = self.parse_pattern()?;
+                if self.current().kind != TokenKind::Comma {
+                    self.expect(TokenKind::CloseParenthesis)?;
+                    return Ok(pattern);
+                }
+                let mut patterns: Vec<Pattern> = vec![pattern];
+                while self.current().kind == TokenKind::Comma {
+                    self.expect(TokenKind::Comma)?;
+                    patterns.push(self.parse_pattern()?);
+                }
+                self.expect(TokenKind::CloseParenthesis)?;
+                Ok(Pattern::Tuple(patterns, span))
+            }
+            TokenKind::Identifier => {
+                let name: String = self.expect(TokenKind::Identifier)?.literal.unwrap();
+                if name == "_" {
+                    return Ok(Pattern::Wildcard(span));
+                }
+                if self.current().kind == TokenKind::Operator && self.current().literal.as_deref() == Some("@") {
+                    self.advance();
+                    let pattern: Pattern = self.parse_single_pattern()?;
+                    return Ok(Pattern::As(name, Box::new(pattern), span));
+                }
+                if self.current().kind != TokenKind::OpenParenthesis {
+                    // Like in Haskell, capitalized names are constructors and the rest bind variables.
+                    if name.starts_with(char::is_uppercase) {
+                        return Ok(Pattern::Constructor(name, vec![], span));
+                    }
+                    return Ok(Pattern::Binding(name, span));
+                }
+                self.expect(TokenKind::OpenParenthesis)?;
+                let mut args: Vec<(Option<String>, Pattern)> = vec![];
+                while self.current().kind != TokenKind::CloseParenthesis {
+                    let mut field: Option<String> = None;
+                    if self.current().kind == TokenKind::Identifier && self.peek(1).kind == TokenKind::Equal {
+                        field = self.expect(TokenKind::Identifier)?.literal;
+                        self.expect(TokenKind::Equal)?;
+                    }
+                    args.push((field, self.parse_pattern()?));
+                    if self.current().kind != TokenKind::CloseParenthesis {
+                        self.expect(TokenKind::Comma)?;
+                    }
+                }
+                let close: Token = self.expect(TokenKind::CloseParenthesis)?;
+                if args.iter().all(|(field, _)| field.is_none()) {
+                    return Ok(Pattern::Constructor(name, args.into_iter().map(|(_, pattern)| pattern).collect(), span));
+                }
+                let mut fields: Vec<(String, Pattern)> = vec![];
+                for (field, pattern) in args {
+                    match (field, pattern) {
+                        (Some(field), pattern) => fields.push((field, pattern)),
+                        (None, Pattern::Binding(field, field_span)) => fields.push((field.clone(), Pattern::Binding(field, field_span))),
+                        _ => return Err(ParseError::invalid("field patterns can't be mixed with positional ones".to_string(), close)),
+                    }
+                }
+                Ok(Pattern::Struct(name, fields, span))
+            }
+            _ => Err(self.unexpected(vec![TokenKind::Identifier, TokenKind::IntegerLiteral, TokenKind::OpenParenthesis])),
+        }
+    }
+
+    // Lets `then` and `else` start a new line at the same indentation as the `if`.
+    fn skip_newline_before(&mut self, kind: TokenKind) {
+        if self.current().kind == TokenKind::Newline && self.peek(1).kind == kind {
+            self.advance();
+        }
+    }
+    // A line normally ends in a newline, but an expression that ends in an indented block
+    // (like a `match`) has already consumed it together with the block's closing dedent.
+    fn expect_line_end(&mut self) -> Result<(), ParseError> {
+        if self.current > 0 && self.tokens[self.current - 1].kind == TokenKind::Dedent {
+            return Ok(());
+        }
+        self.expect(TokenKind::Newline)?;
+        Ok(())
+    }
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, ParseError> {
         let token: Token = self.current();
         if token.kind != kind {
-            panic!("unexpected token: {:?} ({:?}), expected: {:?}", token.kind, token.literal, kind);
+            return Err(ParseError::unexpected(vec![kind], token));
         }
         self.advance();
-        token
+        Ok(token)
+    }
+    // An error for the current token, which should have been one of `expected`.
+    fn unexpected(&mut self, expected: Vec<TokenKind>) -> ParseError {
+        ParseError::unexpected(expected, self.current())
     }
+    // Past the last token this is an `EndOfFile` token at the very end of the input, so running
+    // out of tokens is reported like any other unexpected token.
     fn current(&mut self) -> Token {
         if self.current < self.tokens.len() {
             return self.tokens[self.current].clone();
         }
-        panic!("unexpected end of file")
+        let span: Span = match self.tokens.last() {
+            Some(token) => Span::new(token.span.file, token.span.end, token.span.end),
+            None => Span::new(FileId(0), 0, 0),
+        };
+        Token { kind: TokenKind::EndOfFile, literal: None, span }
+    }
+    // The token `offset` places after the current one.
+    fn peek(&mut self, offset: usize) -> Token {
+        self.current += offset;
+        let token: Token = self.current();
+        self.current -= offset;
+        token
     }
     fn advance(&mut self) {
         if self.current >= self.tokens.len() {
-            panic!("unexpected end of file")
+            return;
+        }
+        match self.tokens[self.current].kind {
+            TokenKind::Indent => self.depth += 1,
+            TokenKind::Dedent => self.depth = self.depth.saturating_sub(1),
+            _ => {}
         }
         self.current += 1;
     }
-}
\ No newline at end of file
+}
+
+// The C++ name of the procedure defining a user operator, `<>` becomes `op_less_greater`.
+pub fn mangle_operator(symbol: &str) -> String {
+    let names: Vec<&str> = symbol.chars().map(|c| match c {
+        '+' => "plus",
+        '-' => "minus",
+        '*' => "asterisk",
+        '/' => "slash",
+        '%' => "percent",
+        '<' => "less",
+        '>' => "greater",
+        '=' => "equal",
+        '!' => "bang",
+        '&' => "ampersand",
+        '|' => "pipe",
+        '^' => "caret",
+        '~' => "tilde",
+        '@' => "at",
+        '$' => "dollar",
+        _ => "symbol",
+    }).collect();
+    format!("op_{}", names.join("_"))
+}
+#[cfg(test)]
+mod tests {
+    use lexer::{layout::Layout, lexer::Lexer, span::FileId};
+    use super::*;
+
+    fn parse(source: &str) -> (Vec<Statement>, Vec<ParseError>) {
+        let mut parser: Parser = Parser::new(Layout::new(Lexer::new(source, FileId(0))).collect());
+        parser.parse()
+    }
+    // The names of the procedures that parsed.
+    fn procedures(statements: &[Statement]) -> Vec<String> {
+        statements.iter().filter_map(|statement| match statement {
+            Statement::Procedure(name, _, _, _, _, _) => Some(name.clone()),
+            _ => None,
+        }).collect()
+    }
+    // The body of the last procedure in `source`, with parentheses around every operation.
+    fn body(source: &str) -> String {
+        let (statements, errors) = parse(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let Some(Statement::Procedure(_, _, _, _, body, _)) = statements.last() else {
+            panic!("expected a procedure, found {:?}", statements.last());
+        };
+        show(body)
+    }
+    fn show(expression: &Expression) -> String {
+        match expression {
+            Expression::Variable(name, _) => name.clone(),
+            Expression::Integer(value, _, _) => value.to_string(),
+            Expression::Binary(left, right, op, _) => format!("({} {} {})", show(left), op.describe().trim_matches('`'), show(right)),
+            Expression::Unary(operand, op, _) => format!("({}{})", op.describe().trim_matches('`'), show(operand)),
+            Expression::Call(callee, _, args, _) => {
+                format!("{}({})", show(callee), args.iter().map(show).collect::<Vec<String>>().join(", "))
+            }
+            Expression::Index(value, index, _) => format!("{}[{}]", show(value), show(index)),
+            Expression::Slice(value, start, end, _) => format!(
+                "{}[{}..{}]",
+                show(value),
+                start.as_deref().map(show).unwrap_or_default(),
+                end.as_deref().map(show).unwrap_or_default()
+            ),
+            expression => panic!("unexpected expression {:?}", expression),
+        }
+    }
+
+    #[test]
+    fn errors_after_the_first_are_reported_too() {
+        let (statements, errors) = parse("module M
+procedure A() -> Int = 1 +
+procedure B() -> Int = 2
+procedure C( -> Int = 3
+procedure D() -> Int =
+    let x = )
+    x
+procedure E() -> Int = 4
+");
+        let messages: Vec<String> = errors.iter().map(|error| error.message.clone()).collect();
+        assert_eq!(messages, vec![
+            "expected an expression, found newline",
+            "expected identifier, found `->`",
+            "expected an expression, found `)`",
+        ]);
+        assert_eq!(errors[1].expected, vec![TokenKind::Identifier]);
+        assert_eq!(errors[1].found.kind, TokenKind::Arrow);
+        assert_eq!(procedures(&statements), vec!["B", "E"]);
+    }
+    #[test]
+    fn multiplication_binds_tighter_than_addition() {
+        assert_eq!(body("module M\nprocedure F() -> Int = 1 + 2 * 3 - 4\n"), "((1 + (2 * 3)) - 4)");
+        assert_eq!(body("module M\nprocedure F() -> Int = -a * b\n"), "((-a) * b)");
+    }
+    #[test]
+    fn logical_operators_group_to_the_right() {
+        assert_eq!(body("module M\nprocedure F() -> Bool = a || b && c || d\n"), "(a || ((b && c) || d))");
+        assert_eq!(body("module M\nprocedure F() -> Bool = a < b && c == d\n"), "((a < b) && (c == d))");
+    }
+    #[test]
+    fn comparisons_do_not_chain() {
+        let (_, errors) = parse("module M\nprocedure F() -> Bool = 1 < 2 < 3\n");
+        let messages: Vec<String> = errors.iter().map(|error| error.message.clone()).collect();
+        assert_eq!(messages, vec!["`<` and `<` are non-associative and can't be chained without parentheses"]);
+    }
+    #[test]
+    fn user_operators_follow_their_fixity_declarations() {
+        assert_eq!(
+            body("module M\ninfixr 5 <>\nprocedure F() -> Int = a <> b <> c\n"),
+            "op_less_greater(a, op_less_greater(b, c))"
+        );
+        assert_eq!(body("module M\ninfixl 1 |>\nprocedure F() -> Int = a + b |> f\n"), "op_pipe_greater((a + b), f)");
+        // Without a declaration an operator is `infixl 9`.
+        assert_eq!(body("module M\nprocedure F() -> Int = a <+> b * c\n"), "(op_less_plus_greater(a, b) * c)");
+        assert_eq!(body("module M\nprocedure F() -> Int = a <+> b <+> c\n"), "op_less_plus_greater(op_less_plus_greater(a, b), c)");
+    }
+    #[test]
+    fn fixity_declarations_need_a_precedence_from_0_to_9() {
+        let (_, errors) = parse("module M\ninfixl 10 <>\n");
+        let messages: Vec<String> = errors.iter().map(|error| error.message.clone()).collect();
+        assert_eq!(messages, vec!["operator precedence must be between 0 and 9"]);
+    }
+    #[test]
+    fn brackets_index_slice_and_call_elements() {
+        assert_eq!(body("module M\nprocedure F() -> Int = xs[i + 1]\n"), "xs[(i + 1)]");
+        assert_eq!(body("module M\nprocedure F() -> [Int] = xs[1..n]\n"), "xs[1..n]");
+        assert_eq!(body("module M\nprocedure F() -> [Int] = xs[..n][1..]\n"), "xs[..n][1..]");
+        assert_eq!(body("module M\nprocedure F() -> Int = fs[i](2)\n"), "fs[i](2)");
+    }
+}
@@ -0,0 +1,42 @@
+#ifndef REAL_BOX_H
+#define REAL_BOX_H
+
+#include <memory>
+
+namespace Data {
+namespace Box {
+
+// A value on the heap, so a data type can hold itself. The value is never changed in place,
+// so copies can share it and still behave like separate values.
+template<typename T>
+class Box {
+public:
+    // Only placeholders, like the unused slots of a List, are default constructed.
+    Box() = default;
+    ~Box() = default;
+
+    Box(T value)
+        : m_value(std::make_shared<T>(std::move(value)))
+    {
+    }
+
+    const T& operator*() const {
+        return *m_value;
+    }
+
+    const T* operator->() const {
+        return m_value.get();
+    }
+
+    operator const T&() const {
+        return *m_value;
+    }
+
+private:
+    std::shared_ptr<const T> m_value;
+};
+
+} // namespace Box
+} // namespace Data
+
+#endif // REAL_BOX_H
@@ -0,0 +1,16 @@
+#ifndef REAL_ENUM_H
+#define REAL_ENUM_H
+
+#include <variant>
+
+namespace Data {
+namespace Enum {
+
+// A data enum holds one of its variant structs, matches `switch` on `index()` to find which.
+template<typename... Variants>
+using Enum = std::variant<Variants...>;
+
+} // namespace Enum
+} // namespace Data
+
+#endif // REAL_ENUM_H
\ No newline at end of file
@@ -0,0 +1,16 @@
+#ifndef REAL_FUNCTION_H
+#define REAL_FUNCTION_H
+
+#include <functional>
+
+namespace Data {
+namespace Function {
+
+// Lambdas, procedures and anything else callable as `R(Args...)`.
+template<typename T>
+using Function = std::function<T>;
+
+} // namespace Function
+} // namespace Data
+
+#endif // REAL_FUNCTION_H
\ No newline at end of file
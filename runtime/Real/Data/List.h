@@ -1,6 +1,9 @@
 #ifndef REAL_LIST_H
 #define REAL_LIST_H
 
+#include <cstdio>
+#include <cstdlib>
+
 namespace Data {
 namespace List {
 
@@ -20,7 +23,9 @@ public:
 
 
     void append(T value) {
-        if (m_size == m_capacity) {
+        if (m_data == nullptr) {
+            m_data = new T[m_capacity];
+        } else if (m_size == m_capacity) {
             m_capacity *= 2;
             T* new_data = new T[m_capacity];
             for (int i = 0; i < m_size; i++) {
@@ -33,11 +38,55 @@ public:
         m_size++;
     }
 
+    void remove(int index) {
+        for (int i = index; i < m_size - 1; i++) {
+            m_data[i] = m_data[i + 1];
+        }
+        m_size--;
+    }
+
+    // Unchecked, used inside `unsafe`.
     T& operator[](int index) {
         return m_data[index];
     }
-    
-    int length() {
+
+    const T& operator[](int index) const {
+        return m_data[index];
+    }
+
+    T& at(int index) {
+        if (index < 0 || index >= m_size) {
+            fprintf(stderr, "index %d out of bounds for list of length %d\n", index, m_size);
+            std::abort();
+        }
+        return m_data[index];
+    }
+
+    List<T> slice(int start) const {
+        return slice(start, m_size);
+    }
+
+    List<T> slice(int start, int end) const {
+        if (start < 0 || end > m_size || start > end) {
+            fprintf(stderr, "slice %d..%d out of bounds for list of length %d\n", start, end, m_size);
+            std::abort();
+        }
+        return unchecked_slice(start, end);
+    }
+
+    List<T> unchecked_slice(int start) const {
+        return unchecked_slice(start, m_size);
+    }
+
+    List<T> unchecked_slice(int start, int end) const {
+        List<T> list;
+        for (int i = start; i < end; i++) {
+            list.append(m_data[i]);
+        }
+        return list;
+    }
+
+    int length() const {
         return m_size;
     }
 
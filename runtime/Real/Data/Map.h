@@ -2,6 +2,7 @@
 #define REAL_MAP_H
 
 #include <Data/List.h>
+#include <Data/Optional.h>
 
 namespace Data {
 namespace Map {
@@ -20,7 +21,7 @@ public:
 
     static Map<K, V> from_list(List::List<K> keys, List::List<V> values) {
         Map<K, V> map;
-        for (int i = 0; i < keys.size(); i++) {
+        for (int i = 0; i < keys.length(); i++) {
             map.insert(keys[i], values[i]);
         }
         return map;
@@ -33,12 +34,33 @@ public:
     }
 
     void insert(K key, V value) {
-        m_keys.push_back(key);
-        m_values.push_back(value);
+        m_keys.append(key);
+        m_values.append(value);
+    }
+
+    // Lookups give an empty optional for a missing key.
+    Optional::Optional<V> at(K key) const {
+        for (int i = 0; i < m_keys.length(); i++) {
+            if (m_keys[i] == key) {
+                return m_values[i];
+            }
+        }
+        return Optional::Optional<V>();
+    }
+
+    // Unchecked, used inside `unsafe`.
+    V& operator[](K key) {
+        for (int i = 0; i < m_keys.length(); i++) {
+            if (m_keys[i] == key) {
+                return m_values[i];
+            }
+        }
+        fprintf(stderr, "key not found in map\n");
+        std::abort();
     }
 
     V get(K key) const {
-        for (int i = 0; i < m_keys.size(); i++) {
+        for (int i = 0; i < m_keys.length(); i++) {
             if (m_keys[i] == key) {
                 return m_values[i];
             }
@@ -47,7 +69,7 @@ public:
     }
 
     bool contains(K key) const {
-        for (int i = 0; i < m_keys.size(); i++) {
+        for (int i = 0; i < m_keys.length(); i++) {
             if (m_keys[i] == key) {
                 return true;
             }
@@ -56,7 +78,7 @@ public:
     }
 
     void remove(K key) {
-        for (int i = 0; i < m_keys.size(); i++) {
+        for (int i = 0; i < m_keys.length(); i++) {
             if (m_keys[i] == key) {
                 m_keys.remove(i);
                 m_values.remove(i);
@@ -66,7 +88,7 @@ public:
     }
 
     int size() const {
-        return m_keys.size();
+        return m_keys.length();
     }
 
 private:
@@ -1,6 +1,10 @@
 #ifndef REAL_STRING_H
 #define REAL_STRING_H
 
+#include <cstdio>
+#include <cstring>
+#include <initializer_list>
+
 namespace Data {
 namespace String {
     
@@ -11,6 +15,22 @@ public:
 
     static String from_cstr(const char* cstr) { return String(cstr); }
 
+    // Joins all the parts into a newly allocated string, this is what interpolation lowers to.
+    static String concat(std::initializer_list<String> parts) {
+        int length = 0;
+        for (const String& part : parts) {
+            length += part.length();
+        }
+        char* buffer = new char[length + 1];
+        int offset = 0;
+        for (const String& part : parts) {
+            std::memcpy(buffer + offset, part.cstr(), part.length());
+            offset += part.length();
+        }
+        buffer[length] = '\0';
+        return String(buffer);
+    }
+
     int length() const {
         int length = 0;
         while (m_cstr[length] != '\0') {
@@ -20,12 +40,36 @@ public:
     }
 
     const char* cstr() const { return m_cstr; }
+
+    bool operator==(const String& other) const { return std::strcmp(m_cstr, other.m_cstr) == 0; }
+    bool operator!=(const String& other) const { return !(*this == other); }
 private:
     String(const char* cstr)
         : m_cstr(cstr) {}
     const char* m_cstr;
 };
 
+template<typename T>
+String format_value(const char* format, T value) {
+    int length = std::snprintf(nullptr, 0, format, value);
+    char* buffer = new char[length + 1];
+    std::snprintf(buffer, length + 1, format, value);
+    return String::from_cstr(buffer);
+}
+
+// Conversions used by string interpolation, objects can provide their own overload.
+inline String to_string(const String& value) { return value; }
+inline String to_string(const char* value) { return String::from_cstr(value); }
+inline String to_string(bool value) { return String::from_cstr(value ? "true" : "false"); }
+inline String to_string(char value) { return format_value("%c", value); }
+inline String to_string(int value) { return format_value("%d", value); }
+inline String to_string(unsigned int value) { return format_value("%u", value); }
+inline String to_string(long value) { return format_value("%ld", value); }
+inline String to_string(unsigned long value) { return format_value("%lu", value); }
+inline String to_string(long long value) { return format_value("%lld", value); }
+inline String to_string(unsigned long long value) { return format_value("%llu", value); }
+inline String to_string(double value) { return format_value("%g", value); }
+
 } // namespace String
 } // namespace Data
 
@@ -1,17 +1,33 @@
 #ifndef REAL_PRELUDE_H
 #define REAL_PRELUDE_H
 
+#include <cstdint>
+#include <cstdlib>
+#include <tuple>
+
 #include <Data/List.h>
+#include <Data/Map.h>
 #include <Data/String.h>
 #include <Data/Optional.h>
+#include <Data/Function.h>
+#include <Data/Enum.h>
+#include <Data/Box.h>
 
 using namespace Data::List;
+using namespace Data::Map;
 using namespace Data::String;
 using namespace Data::Optional;
+using namespace Data::Function;
+using namespace Data::Enum;
+using namespace Data::Box;
 
 using Data::List::List;
+using Data::Map::Map;
 using Data::String::String;
 using Data::Optional::Optional;
+using Data::Function::Function;
+using Data::Enum::Enum;
+using Data::Box::Box;
 
 namespace Prelude {
 
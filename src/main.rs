@@ -3,9 +3,18 @@
 
 use lexer::{
     lexer::Lexer,
-    tokens::Token
+    layout::Layout,
+    tokens::Token,
+    span::{FileId, SourceMap},
+};
+use parser::{
+    error::ParseError,
+    parser::{Parser, Statement, Expression},
+};
+use typechecker::{
+    error::TypeError,
+    typechecker::TypeChecker,
 };
-use parser::parser::{Parser, Statement, Expression};
 use codegen::Codegen;
 fn main() {
     let mut args = std::env::args().skip(1);
@@ -15,13 +24,41 @@ fn main() {
     }
     let source: String = std::fs::read_to_string(filepath.clone()).unwrap();
 
-    let mut lexer: Lexer = Lexer::new(source.clone());
-    let tokens: Vec<Token> = lexer.lex();
+    let mut source_map: SourceMap = SourceMap::new();
+    let file: FileId = source_map.add_file(filepath.clone(), source.clone());
+
+    let mut layout: Layout = Layout::new(Lexer::new(&source, file));
+    let mut parser: Parser = Parser::new(layout.by_ref().collect());
+    if !layout.errors().is_empty() {
+        for error in layout.errors().iter() {
+            eprintln!("{}: error: {}", source_map.display(&error.span), error.message);
+        }
+        std::process::exit(1);
+    }
+
+    let (statements, errors): (Vec<Statement>, Vec<ParseError>) = parser.parse();
+    if !errors.is_empty() {
+        for error in errors.iter() {
+            eprintln!("{}: error: {}", source_map.display(&error.span), error.message);
+        }
+        std::process::exit(1);
+    }
 
-    let mut parser: Parser = Parser::new(tokens);
-    let statements: Vec<Statement> = parser.parse();
+    let mut type_checker: TypeChecker = TypeChecker::new(parser.clone());
+    let errors: Vec<TypeError> = type_checker.check();
+    for warning in type_checker.warnings().iter() {
+        eprintln!("{}: warning: {}", source_map.display(&warning.span), warning.message);
+    }
+    if !errors.is_empty() {
+        for error in errors.iter() {
+            eprintln!("{}: error: {}", source_map.display(&error.span), error.message);
+        }
+        std::process::exit(1);
+    }
 
-    let mut codegen: Codegen = Codegen::new(filepath.clone().split('/').last().unwrap().to_string(), statements.clone());
+    let mut codegen: Codegen = Codegen::new(filepath.clone().split('/').next_back().unwrap().to_string(), statements.clone());
+    codegen.matched_types = type_checker.matched_types();
+    codegen.result_types = type_checker.result_types();
     let cpp_code: String = codegen.codegen_cpp();
     let header_code: String = codegen.codegen_header();
     std::fs::write(filepath.clone().replace(".real", ".cpp"), cpp_code).unwrap();
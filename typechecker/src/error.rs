@@ -0,0 +1,7 @@
+use lexer::span::Span;
+
+#[derive(Debug, Clone)]
+pub struct TypeError {
+    pub message: String,
+    pub span: Span,
+}
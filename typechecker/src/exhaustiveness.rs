@@ -0,0 +1,486 @@
+#![deny(dead_code)]
+#![allow(unused)]
+
+use std::collections::HashMap;
+use crate::error::TypeError;
+use crate::typechecker::{type_base_name, type_name};
+use parser::parser::{EnumVarient, Expression, Pattern, Type};
+use lexer::tokens::TokenKind;
+
+// A pattern reduced to what matters for coverage, names and guards are gone.
+#[derive(Debug, Clone)]
+pub enum Space {
+    Wildcard,
+    Constructor(Constructor, Vec<Space>),
+    Or(Vec<Space>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constructor {
+    // A variant of the named data enum.
+    Variant(String, String),
+    Struct(String),
+    // A tuple with this many elements.
+    Tuple(usize),
+    Bool(bool),
+    // Any other literal, written out so equal literals compare equal.
+    Literal(String),
+}
+
+// Checks `match` cases against the `data` declarations, following Maranget's "Warnings for
+// pattern matching": a case is unreachable when it isn't useful after the cases before it,
+// and a match is exhaustive when a wildcard isn't useful after all of them.
+pub struct Coverage<'a> {
+    enums: &'a HashMap<String, Vec<EnumVarient>>,
+    structs: &'a HashMap<String, Vec<(String, Type)>>,
+    pub errors: Vec<TypeError>,
+}
+
+impl<'a> Coverage<'a> {
+    pub fn new(enums: &'a HashMap<String, Vec<EnumVarient>>, structs: &'a HashMap<String, Vec<(String, Type)>>) -> Self {
+        Self {
+            enums,
+            structs,
+            errors: vec![],
+        }
+    }
+
+    // `t` is the type of the matched value when it's known, the names of the variants decide
+    // which data enum a pattern is about otherwise.
+    pub fn lower(&mut self, pattern: &Pattern, t: Option<&Type>) -> Space {
+        match pattern {
+            Pattern::Wildcard(_) | Pattern::Binding(_, _) => Space::Wildcard,
+            Pattern::As(_, pattern, _) => self.lower(pattern, t),
+            Pattern::Or(alternatives, _) => Space::Or(alternatives.iter().map(|p| self.lower(p, t)).collect()),
+            Pattern::Literal(literal, _) => match literal_name(literal) {
+                Some(name) if name == "true" || name == "false" => Space::Constructor(Constructor::Bool(name == "true"), vec![]),
+                Some(name) => Space::Constructor(Constructor::Literal(name), vec![]),
+                None => Space::Wildcard,
+            },
+            Pattern::Constructor(name, args, span) if self.structs.contains_key(name) => {
+                let fields: Vec<Type> = self.field_types(&Constructor::Struct(name.clone()));
+                if args.len() != fields.len() {
+                    self.error(format!("`{}` has {} fields but the pattern has {}", name, fields.len(), args.len()), span.clone());
+                    return Space::Wildcard;
+                }
+                let args: Vec<Space> = args.iter().zip(fields.iter()).map(|(arg, t)| self.lower(arg, Some(t))).collect();
+                Space::Constructor(Constructor::Struct(name.clone()), args)
+            }
+            Pattern::Constructor(name, args, span) => {
+                let Some(enum_name) = self.enum_of(name, t) else {
+                    match t {
+                        Some(t) if base_name(t).is_some_and(|t| self.enums.contains_key(t)) => {
+                            self.error(format!("`{}` is not a variant of `{}`", name, type_name(t)), span.clone())
+                        }
+                        _ if self.enums.values().filter(|variants| has_variant(variants, name)).count() > 1 => {
+                            self.error(format!("`{}` is a variant of several data enums; annotate the matched value", name), span.clone())
+                        }
+                        _ => self.error(format!("`{}` is not a variant of any data enum", name), span.clone()),
+                    }
+                    return Space::Wildcard;
+                };
+                let constructor: Constructor = Constructor::Variant(enum_name, name.clone());
+                let fields: Vec<Type> = self.field_types(&constructor);
+                if args.len() != fields.len() {
+                    self.error(format!("`{}` has {} fields but the pattern has {}", name, fields.len(), args.len()), span.clone());
+                    return Space::Wildcard;
+                }
+                let args: Vec<Space> = args.iter().zip(fields.iter()).map(|(arg, t)| self.lower(arg, Some(t))).collect();
+                Space::Constructor(constructor, args)
+            }
+            Pattern::Tuple(patterns, span) => {
+                let types: Option<&Vec<Type>> = match t {
+                    Some(Type::Tuple(types, _)) => Some(types),
+                    _ => None,
+                };
+                if let Some(types) = types.as_ref().filter(|types| types.len() != patterns.len()) {
+                    self.error(format!("the tuple has {} elements but the pattern has {}", types.len(), patterns.len()), span.clone());
+                    return Space::Wildcard;
+                }
+                let args: Vec<Space> = patterns.iter().enumerate()
+                    .map(|(i, pattern)| self.lower(pattern, types.map(|types| &types[i])))
+                    .collect();
+                Space::Constructor(Constructor::Tuple(patterns.len()), args)
+            }
+            Pattern::Struct(name, fields, span) => {
+                let Some(declared) = self.structs.get(name) else {
+                    self.error(format!("`{}` is not a data struct", name), span.clone());
+                    return Space::Wildcard;
+                };
+                for (field, _) in fields.iter() {
+                    if !declared.iter().any(|(declared, _)| declared == field) {
+                        self.error(format!("`{}` has no field `{}`", name, field), span.clone());
+                    }
+                }
+                let declared: Vec<(String, Type)> = declared.clone();
+                let args: Vec<Space> = declared.iter().map(|(field, t)| {
+                    match fields.iter().find(|(name, _)| name == field) {
+                        Some((_, pattern)) => self.lower(pattern, Some(t)),
+                        None => Space::Wildcard,
+                    }
+                }).collect();
+                Space::Constructor(Constructor::Struct(name.clone()), args)
+            }
+        }
+    }
+
+    // A list of values matched by `row` but by none of `rows`, or `None` when there is none,
+    // which makes `row` useless after them. `types` are the types of the columns where known.
+    pub fn useful(&self, rows: &[Vec<Space>], row: &[Space], types: &[Option<Type>]) -> Option<Vec<Space>> {
+        if row.is_empty() {
+            return if rows.is_empty() { Some(vec![]) } else { None };
+        }
+        match &row[0] {
+            Space::Or(alternatives) => alternatives.iter().find_map(|alternative| {
+                let mut row: Vec<Space> = row.to_vec();
+                row[0] = alternative.clone();
+                self.useful(rows, &row, types)
+            }),
+            Space::Constructor(constructor, args) => {
+                let rows: Vec<Vec<Space>> = rows.iter().flat_map(|r| self.specialize(r, constructor)).collect();
+                let mut specialized: Vec<Space> = args.clone();
+                specialized.extend_from_slice(&row[1..]);
+                let witness: Vec<Space> = self.useful(&rows, &specialized, &self.subtypes(constructor, types))?;
+                Some(rebuild(constructor, args.len(), witness))
+            }
+            Space::Wildcard => {
+                let heads: Vec<Constructor> = rows.iter().flat_map(|r| heads(&r[0])).collect();
+                let all: Option<Vec<Constructor>> = self.constructors(types[0].as_ref(), &heads);
+                let missing: Option<Constructor> = match &all {
+                    Some(all) => all.iter().find(|c| !heads.contains(c)).cloned(),
+                    None => None,
+                };
+                if let (Some(all), None) = (&all, &missing) {
+                    // Every constructor appears, so the wildcard is useful only under one of them.
+                    return all.iter().find_map(|constructor| {
+                        let arity: usize = self.arity(constructor);
+                        let rows: Vec<Vec<Space>> = rows.iter().flat_map(|r| self.specialize(r, constructor)).collect();
+                        let mut specialized: Vec<Space> = vec![Space::Wildcard; arity];
+                        specialized.extend_from_slice(&row[1..]);
+                        let witness: Vec<Space> = self.useful(&rows, &specialized, &self.subtypes(constructor, types))?;
+                        Some(rebuild(constructor, arity, witness))
+                    });
+                }
+                let rows: Vec<Vec<Space>> = rows.iter().flat_map(default).collect();
+                let mut witness: Vec<Space> = self.useful(&rows, &row[1..], &types[1..])?;
+                let head: Space = match missing {
+                    Some(constructor) => {
+                        let arity: usize = self.arity(&constructor);
+                        Space::Constructor(constructor, vec![Space::Wildcard; arity])
+                    }
+                    None => Space::Wildcard,
+                };
+                witness.insert(0, head);
+                Some(witness)
+            }
+        }
+    }
+
+    // The rows that can match `constructor`, with its fields in place of the first column.
+    fn specialize(&self, row: &[Space], constructor: &Constructor) -> Vec<Vec<Space>> {
+        match &row[0] {
+            Space::Wildcard => {
+                let mut specialized: Vec<Space> = vec![Space::Wildcard; self.arity(constructor)];
+                specialized.extend_from_slice(&row[1..]);
+                vec![specialized]
+            }
+            Space::Constructor(head, args) if head == constructor => {
+                let mut specialized: Vec<Space> = args.clone();
+                specialized.extend_from_slice(&row[1..]);
+                vec![specialized]
+            }
+            Space::Constructor(_, _) => vec![],
+            Space::Or(alternatives) => alternatives.iter().flat_map(|alternative| {
+                let mut row: Vec<Space> = row.to_vec();
+                row[0] = alternative.clone();
+                self.specialize(&row, constructor)
+            }).collect(),
+        }
+    }
+
+    // Every constructor of the first column's type, or `None` when there are too many to list.
+    fn constructors(&self, t: Option<&Type>, heads: &[Constructor]) -> Option<Vec<Constructor>> {
+        let enum_name: Option<String> = heads.iter().find_map(|head| match head {
+            Constructor::Variant(enum_name, _) => Some(enum_name.clone()),
+            _ => None,
+        }).or(t.and_then(base_name).filter(|t| self.enums.contains_key(*t)).map(str::to_string));
+        if let Some(enum_name) = enum_name {
+            let variants: &Vec<EnumVarient> = self.enums.get(&enum_name)?;
+            return Some(variants.iter().map(|variant| match variant {
+                EnumVarient::Unit(name, _) | EnumVarient::Tuple(name, _, _) => Constructor::Variant(enum_name.clone(), name.clone()),
+            }).collect());
+        }
+        let struct_name: Option<String> = heads.iter().find_map(|head| match head {
+            Constructor::Struct(name) => Some(name.clone()),
+            _ => None,
+        }).or(t.and_then(base_name).filter(|t| self.structs.contains_key(*t)).map(str::to_string));
+        if let Some(struct_name) = struct_name {
+            return Some(vec![Constructor::Struct(struct_name)]);
+        }
+        let arity: Option<usize> = heads.iter().find_map(|head| match head {
+            Constructor::Tuple(arity) => Some(*arity),
+            _ => None,
+        }).or(match t {
+            Some(Type::Tuple(types, _)) => Some(types.len()),
+            _ => None,
+        });
+        if let Some(arity) = arity {
+            return Some(vec![Constructor::Tuple(arity)]);
+        }
+        if matches!(t, Some(Type::Bool(_))) || heads.iter().any(|head| matches!(head, Constructor::Bool(_))) {
+            return Some(vec![Constructor::Bool(true), Constructor::Bool(false)]);
+        }
+        None
+    }
+
+    fn enum_of(&self, variant: &str, t: Option<&Type>) -> Option<String> {
+        if let Some(t) = t.and_then(base_name) {
+            if self.enums.contains_key(t) {
+                return self.enums.get(t).filter(|variants| has_variant(variants, variant)).map(|_| t.to_string());
+            }
+        }
+        let candidates: Vec<&String> = self.enums.iter().filter(|(_, variants)| has_variant(variants, variant)).map(|(name, _)| name).collect();
+        if candidates.len() == 1 { Some(candidates[0].clone()) } else { None }
+    }
+
+    // The types of a constructor's fields, tuples have theirs in the matched value's type.
+    fn field_types(&self, constructor: &Constructor) -> Vec<Type> {
+        match constructor {
+            Constructor::Variant(enum_name, name) => self.enums.get(enum_name).into_iter().flatten().find_map(|v| match v {
+                EnumVarient::Tuple(variant, types, _) if variant == name => Some(types.clone()),
+                EnumVarient::Unit(variant, _) if variant == name => Some(vec![]),
+                _ => None,
+            }).unwrap_or_default(),
+            Constructor::Struct(name) => self.structs.get(name).into_iter().flatten().map(|(_, t)| t.clone()).collect(),
+            Constructor::Tuple(_) | Constructor::Bool(_) | Constructor::Literal(_) => vec![],
+        }
+    }
+    fn arity(&self, constructor: &Constructor) -> usize {
+        match constructor {
+            Constructor::Tuple(arity) => *arity,
+            _ => self.field_types(constructor).len(),
+        }
+    }
+    fn subtypes(&self, constructor: &Constructor, types: &[Option<Type>]) -> Vec<Option<Type>> {
+        let mut subtypes: Vec<Option<Type>> = match constructor {
+            Constructor::Tuple(arity) => match &types[0] {
+                Some(Type::Tuple(types, _)) => types.iter().cloned().map(Some).collect(),
+                _ => vec![None; *arity],
+            },
+            _ => self.field_types(constructor).into_iter().map(Some).collect(),
+        };
+        subtypes.extend_from_slice(&types[1..]);
+        subtypes
+    }
+
+    fn error(&mut self, message: String, span: lexer::span::Span) {
+        self.errors.push(TypeError { message, span });
+    }
+}
+
+// The rows of the default matrix, the ones whose first column matches anything.
+fn default(row: &Vec<Space>) -> Vec<Vec<Space>> {
+    match &row[0] {
+        Space::Wildcard => vec![row[1..].to_vec()],
+        Space::Constructor(_, _) => vec![],
+        Space::Or(alternatives) => alternatives.iter().flat_map(|alternative| {
+            let mut row: Vec<Space> = row.clone();
+            row[0] = alternative.clone();
+            default(&row)
+        }).collect(),
+    }
+}
+
+fn heads(space: &Space) -> Vec<Constructor> {
+    match space {
+        Space::Wildcard => vec![],
+        Space::Constructor(constructor, _) => vec![constructor.clone()],
+        Space::Or(alternatives) => alternatives.iter().flat_map(heads).collect(),
+    }
+}
+
+// Puts the first `arity` values of a witness back under `constructor`.
+fn rebuild(constructor: &Constructor, arity: usize, mut witness: Vec<Space>) -> Vec<Space> {
+    let rest: Vec<Space> = witness.split_off(arity);
+    let mut rebuilt: Vec<Space> = vec![Space::Constructor(constructor.clone(), witness)];
+    rebuilt.extend(rest);
+    rebuilt
+}
+
+// `Result[Int, Error]` is a `Result`.
+fn base_name(t: &Type) -> Option<&str> {
+    match t {
+        Type::GenericType(base, _, _) => type_base_name(base),
+        t => type_base_name(t),
+    }
+}
+
+fn has_variant(variants: &[EnumVarient], variant: &str) -> bool {
+    variants.iter().any(|v| match v {
+        EnumVarient::Unit(name, _) | EnumVarient::Tuple(name, _, _) => name == variant,
+    })
+}
+
+fn literal_name(literal: &Expression) -> Option<String> {
+    match literal {
+        Expression::Integer(value, _, _) => Some(value.to_string()),
+        Expression::Float(value, _) => Some(format!("{:?}", value)),
+        Expression::Bool(value, _) => Some(value.to_string()),
+        Expression::Char(value, _) => Some(format!("{:?}", value)),
+        Expression::String(value, _) => Some(format!("{:?}", value)),
+        Expression::Unary(operand, TokenKind::Minus, _) => literal_name(operand).map(|name| format!("-{}", name)),
+        _ => None,
+    }
+}
+
+// A value the way it would be written as a pattern, for error messages.
+pub fn display(space: &Space) -> String {
+    match space {
+        Space::Wildcard => "_".to_string(),
+        Space::Or(alternatives) => alternatives.iter().map(display).collect::<Vec<String>>().join(" | "),
+        Space::Constructor(constructor, args) => {
+            let name: String = match constructor {
+                Constructor::Variant(_, name) | Constructor::Struct(name) | Constructor::Literal(name) => name.clone(),
+                Constructor::Bool(value) => value.to_string(),
+                Constructor::Tuple(_) => String::new(),
+            };
+            if args.is_empty() {
+                name
+            } else {
+                format!("{}({})", name, args.iter().map(display).collect::<Vec<String>>().join(", "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lexer::{layout::Layout, lexer::Lexer, span::FileId};
+    use parser::parser::Parser;
+    use crate::typechecker::TypeChecker;
+
+    // The error and warning messages for `source`.
+    fn check(source: &str) -> (Vec<String>, Vec<String>) {
+        let mut parser: Parser = Parser::new(Layout::new(Lexer::new(source, FileId(0))).collect());
+        let (_, errors) = parser.parse();
+        assert!(errors.is_empty(), "{:?}", errors);
+        let mut type_checker: TypeChecker = TypeChecker::new(parser);
+        let errors: Vec<String> = type_checker.check().into_iter().map(|error| error.message).collect();
+        let warnings: Vec<String> = type_checker.warnings().into_iter().map(|warning| warning.message).collect();
+        (errors, warnings)
+    }
+
+    #[test]
+    fn missing_variant_is_the_witness() {
+        let (errors, _) = check("module M
+data Shape =
+    | Circle(Int)
+    | Square(Int)
+    | Empty
+procedure F(s: Shape) -> Int = match s with
+    | Circle(r) -> r
+    | Empty -> 0
+");
+        assert_eq!(errors, vec!["non-exhaustive match, `Square(_)` is not covered"]);
+    }
+    #[test]
+    fn witness_names_the_missing_nested_value() {
+        let (errors, _) = check("module M
+data Shape =
+    | Circle(Int)
+    | Square(Int)
+    | Empty
+data Tagged =
+    | Tag(Shape, Bool)
+procedure F(t: Tagged) -> Int = match t with
+    | Tag(Circle(_), _) | Tag(_, true) -> 0
+    | Tag(Empty, _) -> 1
+");
+        assert_eq!(errors, vec!["non-exhaustive match, `Tag(Square(_), false)` is not covered"]);
+    }
+    #[test]
+    fn witness_names_the_missing_tuple() {
+        let (errors, _) = check("module M
+data Shape =
+    | Circle(Int)
+    | Square(Int)
+    | Empty
+procedure F(p: (Shape, Bool)) -> Int = match p with
+    | (Circle(_), _) | (_, true) -> 0
+    | (Empty, _) -> 1
+");
+        assert_eq!(errors, vec!["non-exhaustive match, `(Square(_), false)` is not covered"]);
+    }
+    #[test]
+    fn guarded_cases_do_not_cover() {
+        let (errors, _) = check("module M
+procedure F(b: Bool) -> Int = match b with
+    | true -> 0
+    | false if true -> 1
+");
+        assert_eq!(errors, vec!["non-exhaustive match, `false` is not covered"]);
+    }
+    #[test]
+    fn exhaustive_match_has_no_diagnostics() {
+        let (errors, warnings) = check("module M
+data Shape =
+    | Circle(Int)
+    | Square(Int)
+procedure F(s: Shape) -> Int = match s with
+    | Circle(r) | Square(r) -> r
+");
+        assert!(errors.is_empty() && warnings.is_empty(), "{:?} {:?}", errors, warnings);
+    }
+    #[test]
+    fn case_after_covering_cases_is_unreachable() {
+        let (errors, warnings) = check("module M
+procedure F(n: Int) -> Int = match n with
+    | 0 -> 0
+    | _ -> 1
+    | 2 -> 2
+");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(warnings, vec!["unreachable match case, the cases before it match everything it does"]);
+    }
+    #[test]
+    fn else_after_exhaustive_cases_is_redundant() {
+        let (errors, warnings) = check("module M
+procedure F(b: Bool) -> Int = match b with
+    | true -> 0
+    | false -> 1
+    | else -> 2
+");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(warnings, vec!["`else` case can never be reached, the cases before it match every value"]);
+    }
+    #[test]
+    fn else_after_partial_cases_is_reachable() {
+        let (errors, warnings) = check("module M
+procedure F(n: Int) -> Int = match n with
+    | 0 -> 0
+    | else -> 1
+");
+        assert!(errors.is_empty() && warnings.is_empty(), "{:?} {:?}", errors, warnings);
+    }
+    #[test]
+    fn variant_of_several_enums_needs_the_matched_type() {
+        let source: &str = "module M
+data OptA =
+    | None
+    | SomeA(Int)
+data OptB =
+    | None
+    | SomeB(Bool)
+";
+        let (errors, _) = check(&format!("{}procedure F[T](o: T) -> Int = match o with
+    | None -> 0
+    | else -> 1
+", source));
+        assert_eq!(errors, vec!["`None` is a variant of several data enums; annotate the matched value"]);
+        let (errors, _) = check(&format!("{}procedure F(o: OptB) -> Int = match o with
+    | None -> 0
+    | SomeB(_) -> 1
+", source));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+}
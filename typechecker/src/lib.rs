@@ -1 +1,3 @@
+pub mod error;
+pub mod exhaustiveness;
 pub mod typechecker;
\ No newline at end of file
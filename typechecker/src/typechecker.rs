@@ -1,12 +1,36 @@
 #![deny(dead_code)]
 #![allow(unused)]
 
-use parser::parser::Parser;
+use std::collections::HashMap;
+use crate::error::TypeError;
+use crate::exhaustiveness::{display, Coverage, Space};
+use lexer::span::Span;
+use lexer::tokens::TokenKind;
+use parser::parser::{EnumVarient, Expression, MatchCase, Parser, Pattern, Statement, StringPart, Type};
 
 #[derive(Debug, Clone)]
 pub struct TypeChecker {
     parser: Parser,
     current: usize,
+
+    // Every alias by name, with its generic parameter names and the type it stands for.
+    aliases: HashMap<String, (Vec<String>, Type)>,
+    // Every trait by name, with its generic parameter names and members.
+    traits: HashMap<String, (Vec<String>, Vec<Statement>)>,
+    // The generic parameter names and return type of every top-level procedure, by name.
+    procedures: HashMap<String, Vec<(Vec<String>, Type)>>,
+    // Every data enum's variants and every data struct, with its generic parameter names and
+    // fields. Aliases in their types are already resolved.
+    enums: HashMap<String, Vec<EnumVarient>>,
+    structs: HashMap<String, (Vec<String>, Vec<(String, Type)>)>,
+    // By the span of each `match`, the type of the value it matches and the type it gives,
+    // where they are known. Code generation needs them to tell variants and closures apart.
+    matched_types: HashMap<Span, Type>,
+    result_types: HashMap<Span, Type>,
+    errors: Vec<TypeError>,
+    warnings: Vec<TypeError>,
+    // Inside `unsafe`, where indexing isn't checked and map lookups don't give optionals.
+    in_unsafe: bool,
 }
 
 impl TypeChecker {
@@ -14,6 +38,770 @@ impl TypeChecker {
         Self {
             parser,
             current: 0,
+
+            aliases: HashMap::new(),
+            traits: HashMap::new(),
+            procedures: HashMap::new(),
+            enums: HashMap::new(),
+            structs: HashMap::new(),
+            matched_types: HashMap::new(),
+            result_types: HashMap::new(),
+            errors: vec![],
+            warnings: vec![],
+            in_unsafe: false,
+        }
+    }
+
+    pub fn check(&mut self) -> Vec<TypeError> {
+        let statements: Vec<Statement> = self.parser.statements().to_vec();
+        // Aliases come first, every other type is resolved through them.
+        for statement in statements.iter() {
+            match statement.undocumented() {
+                Statement::Alias(name, _, t, _) => {
+                    self.aliases.insert(name.clone(), (vec![], t.clone()));
+                }
+                Statement::GenericAlias(name, _, generics, t, _) => {
+                    self.aliases.insert(name.clone(), (parameter_names(generics), t.clone()));
+                }
+                _ => {}
+            }
+        }
+        for statement in statements.iter() {
+            match statement.undocumented() {
+                Statement::Trait(name, _, members, _) => {
+                    self.traits.insert(name.clone(), (vec![], members.clone()));
+                }
+                Statement::GenericTrait(name, _, generics, members, _) => {
+                    self.traits.insert(name.clone(), (parameter_names(generics), members.clone()));
+                }
+                Statement::Procedure(name, _, _, return_type, _, _) => {
+                    let return_type: Type = self.resolve(return_type);
+                    self.procedures.entry(name.clone()).or_default().push((vec![], return_type));
+                }
+                Statement::GenericProcedure(name, _, generics, _, return_type, _, _) => {
+                    let return_type: Type = self.resolve(return_type);
+                    self.procedures.entry(name.clone()).or_default().push((parameter_names(generics), return_type));
+                }
+                Statement::DataEnum(name, _, variants, _) | Statement::GenericDataEnum(name, _, _, variants, _) => {
+                    let variants: Vec<EnumVarient> = variants.iter().map(|variant| match variant {
+                        EnumVarient::Tuple(variant, types, span) => {
+                            EnumVarient::Tuple(variant.clone(), types.iter().map(|t| self.resolve(t)).collect(), span.clone())
+                        }
+                        variant => variant.clone(),
+                    }).collect();
+                    self.enums.insert(name.clone(), variants);
+                }
+                Statement::DataStruct(name, _, fields, _) => {
+                    let fields: Vec<(String, Type)> = fields.iter().map(|(field, t)| (field.clone(), self.resolve(t))).collect();
+                    self.structs.insert(name.clone(), (vec![], fields));
+                }
+                Statement::GenericDataStruct(name, _, generics, fields, _) => {
+                    let fields: Vec<(String, Type)> = fields.iter().map(|(field, t)| (field.clone(), self.resolve(t))).collect();
+                    self.structs.insert(name.clone(), (parameter_names(generics), fields));
+                }
+                _ => {}
+            }
+        }
+        for statement in statements.iter() {
+            match statement.undocumented() {
+                Statement::Object(name, _, _, _, members, _) | Statement::GenericObject(name, _, _, _, _, members, _) => {
+                    self.check_conformance(name, members);
+                    for member in members.iter() {
+                        self.check_procedure(member);
+                    }
+                }
+                statement => self.check_procedure(statement),
+            }
+        }
+        self.errors.clone()
+    }
+    // Problems that don't stop compilation, available once `check` has run.
+    pub fn warnings(&self) -> Vec<TypeError> {
+        self.warnings.clone()
+    }
+    pub fn matched_types(&self) -> HashMap<Span, Type> {
+        self.matched_types.clone()
+    }
+    pub fn result_types(&self) -> HashMap<Span, Type> {
+        self.result_types.clone()
+    }
+
+    // Every `| of Trait` member of an object needs a procedure matching each of the trait's
+    // signatures, while procedures with a default body in the trait may be left out.
+    fn check_conformance(&mut self, object: &str, members: &[Statement]) {
+        for member in members.iter() {
+            let Statement::Of(trait_type, span) = member.undocumented() else {
+                continue;
+            };
+            let (name, arguments): (String, Vec<Type>) = match trait_type {
+                Type::GenericType(base, arguments, _) => (type_name(base), arguments.clone()),
+                t => (type_name(t), vec![]),
+            };
+            let Some((parameters, required)) = self.traits.get(&name).cloned() else {
+                self.error(format!("`{}` is not a trait", name), span.clone());
+                continue;
+            };
+            // A trait over a single type can leave it out, which then means the object itself.
+            let mut substitution: HashMap<String, Type> = HashMap::new();
+            if arguments.is_empty() && parameters.len() == 1 {
+                substitution.insert(parameters[0].clone(), Type::Object(object.to_string(), span.clone()));
+            } else if arguments.len() == parameters.len() {
+                for (parameter, argument) in parameters.iter().zip(arguments.iter()) {
+                    substitution.insert(parameter.clone(), self.resolve(argument));
+                }
+            } else {
+                self.error(format!("trait `{}` takes {} type arguments but {} were given", name, parameters.len(), arguments.len()), span.clone());
+                continue;
+            }
+            for procedure in required.iter() {
+                let (procedure_name, expected, is_default): (String, Type, bool) = match procedure.undocumented() {
+                    Statement::Signature(procedure_name, _, args, return_type, span) => {
+                        (procedure_name.clone(), self.signature(args, return_type, &substitution, span), false)
+                    }
+                    Statement::Procedure(procedure_name, _, args, return_type, _, span) => {
+                        (procedure_name.clone(), self.signature(args, return_type, &substitution, span), true)
+                    }
+                    _ => continue,
+                };
+                let implementation: Option<&Statement> = members.iter().find(|member| {
+                    matches!(member.undocumented(), Statement::Procedure(n, ..) if *n == procedure_name)
+                });
+                match implementation.map(|member| member.undocumented()) {
+                    Some(Statement::Procedure(_, _, args, return_type, _, procedure_span)) => {
+                        let found: Type = self.signature(args, return_type, &HashMap::new(), procedure_span);
+                        if !same_type(&found, &expected) {
+                            self.error(format!(
+                                "`{}` has type `{}` but trait `{}` requires `{}`",
+                                procedure_name, type_name(&found), name, type_name(&expected)
+                            ), procedure_span.clone());
+                        }
+                    }
+                    _ if !is_default => {
+                        self.error(format!("`{}` is missing `{}` required by trait `{}`", object, procedure_name, name), span.clone());
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn check_procedure(&mut self, statement: &Statement) {
+        match statement.undocumented() {
+            Statement::Procedure(_, _, args, return_type, body, _) | Statement::GenericProcedure(_, _, _, args, return_type, body, _) => {
+                let mut locals: HashMap<String, Type> = HashMap::new();
+                for (name, t) in args.iter() {
+                    locals.insert(name.clone(), self.resolve(t));
+                }
+                self.check_expression(body, &locals);
+                let return_type: Type = self.resolve(return_type);
+                self.expect_type(body, &return_type);
+            }
+            _ => {}
+        }
+    }
+
+    // Checks `if` expressions anywhere in `expression`, returning its type where it is simple
+    // to tell. Anything unknown is `None` and never reported, C++ still checks it later.
+    fn check_expression(&mut self, expression: &Expression, locals: &HashMap<String, Type>) -> Option<Type> {
+        let span: Span = expression.span();
+        match expression {
+            Expression::Integer(..) => Some(Type::Int(span)),
+            Expression::Float(..) => Some(Type::Float(span)),
+            Expression::Bool(..) => Some(Type::Bool(span)),
+            Expression::Char(..) => Some(Type::Char(span)),
+            Expression::String(..) => Some(Type::Unknown("String".to_string(), span)),
+            Expression::Interpolated(parts, _) => {
+                for part in parts.iter() {
+                    if let StringPart::Expression(expression) = part {
+                        self.check_expression(expression, locals);
+                    }
+                }
+                Some(Type::Unknown("String".to_string(), span))
+            }
+            Expression::Variable(name, _) => locals.get(name).cloned(),
+            Expression::If(condition, then_branch, else_branch, span) => {
+                let condition_type: Option<Type> = self.check_expression(condition, locals);
+                if let Some(condition_type) = condition_type.filter(|t| !matches!(t, Type::Bool(_))) {
+                    self.error(format!("`if` condition must be `Bool`, found `{}`", type_name(&condition_type)), condition.span());
+                }
+                let then_type: Option<Type> = self.check_expression(then_branch, locals);
+                let else_type: Option<Type> = self.check_expression(else_branch, locals);
+                match (then_type, else_type) {
+                    (Some(then_type), Some(else_type)) if !same_type(&then_type, &else_type) => {
+                        self.error(format!(
+                            "`if` branches have different types `{}` and `{}`",
+                            type_name(&then_type), type_name(&else_type)
+                        ), span.clone());
+                        None
+                    }
+                    (Some(t), _) | (_, Some(t)) => Some(t),
+                    _ => None,
+                }
+            }
+            Expression::Binary(left, right, op, _) => {
+                let left: Option<Type> = self.check_expression(left, locals);
+                let right: Option<Type> = self.check_expression(right, locals);
+                match op {
+                    TokenKind::EqualEqual | TokenKind::BangEqual |
+                    TokenKind::Less | TokenKind::LessEqual |
+                    TokenKind::Greater | TokenKind::GreaterEqual |
+                    TokenKind::AmpersandAmpersand | TokenKind::PipePipe => Some(Type::Bool(span)),
+                    _ if matches!(left, Some(Type::Float(_))) || matches!(right, Some(Type::Float(_))) => Some(Type::Float(span)),
+                    _ => left,
+                }
+            }
+            Expression::Unary(operand, op, _) => {
+                let operand: Option<Type> = self.check_expression(operand, locals);
+                match op {
+                    TokenKind::Bang => Some(Type::Bool(span)),
+                    _ => operand,
+                }
+            }
+            Expression::Unsafe(operand, _) => {
+                let in_unsafe: bool = self.in_unsafe;
+                self.in_unsafe = true;
+                let t: Option<Type> = self.check_expression(operand, locals);
+                self.in_unsafe = in_unsafe;
+                t
+            }
+            // Lists are indexed by `Int`, maps by their key type and give `V?` as the key may be missing.
+            Expression::Index(value, index, _) => {
+                let value_type: Option<Type> = self.check_expression(value, locals);
+                let index_type: Option<Type> = self.check_expression(index, locals);
+                match value_type? {
+                    Type::Array(element, _) => {
+                        if let Some(index_type) = index_type.filter(|t| !matches!(t, Type::Int(_))) {
+                            self.error(format!("index must be `Int`, found `{}`", type_name(&index_type)), index.span());
+                        }
+                        Some(*element)
+                    }
+                    Type::GenericType(base, arguments, _) if type_name(&base) == "Map" && arguments.len() == 2 => {
+                        if let Some(index_type) = index_type.filter(|t| !same_type(t, &arguments[0])) {
+                            self.error(format!(
+                                "map key must be `{}`, found `{}`",
+                                type_name(&arguments[0]), type_name(&index_type)
+                            ), index.span());
+                        }
+                        let value: Type = arguments[1].clone();
+                        Some(if self.in_unsafe { value } else { Type::Optional(Box::new(value), span) })
+                    }
+                    _ => None,
+                }
+            }
+            Expression::Slice(value, start, end, _) => {
+                let value_type: Option<Type> = self.check_expression(value, locals);
+                for bound in start.iter().chain(end.iter()) {
+                    let bound_type: Option<Type> = self.check_expression(bound, locals);
+                    if let Some(bound_type) = bound_type.filter(|t| !matches!(t, Type::Int(_))) {
+                        self.error(format!("slice bound must be `Int`, found `{}`", type_name(&bound_type)), bound.span());
+                    }
+                }
+                value_type.filter(|t| matches!(t, Type::Array(..)))
+            }
+            Expression::Call(callee, types, args, _) => {
+                for arg in args.iter() {
+                    self.check_expression(arg, locals);
+                }
+                let Expression::Variable(name, _) = callee.as_ref() else {
+                    self.check_expression(callee, locals);
+                    return None;
+                };
+                if locals.contains_key(name) {
+                    return None;
+                }
+                // Overloads and generic procedures without type arguments would need real inference.
+                match self.procedures.get(name).map(|overloads| overloads.as_slice()) {
+                    Some([(parameters, return_type)]) if parameters.len() == types.len() => {
+                        let substitution: HashMap<String, Type> = parameters.iter().cloned()
+                            .zip(types.iter().map(|t| self.resolve(t)))
+                            .collect();
+                        Some(substitute(return_type, &substitution))
+                    }
+                    _ => None,
+                }
+            }
+            Expression::MethodCall(receiver, _, _, args, _) => {
+                self.check_expression(receiver, locals);
+                for arg in args.iter() {
+                    self.check_expression(arg, locals);
+                }
+                None
+            }
+            Expression::Member(object, field, span) => {
+                let object_type: Type = self.check_expression(object, locals)?;
+                let fields: Vec<(String, Option<Type>)> = self.struct_fields(&object_type)?;
+                match fields.into_iter().find(|(name, _)| name == field) {
+                    Some((_, t)) => t,
+                    None => {
+                        self.error(format!("`{}` has no field `{}`", type_name(&object_type), field), span.clone());
+                        None
+                    }
+                }
+            }
+            // Positional fields come first and go in declaration order, named ones fill in the rest.
+            Expression::Construct(name, types, fields, span) => {
+                let t: Type = match types.is_empty() {
+                    true => Type::DataStruct(name.clone(), span.clone()),
+                    false => {
+                        let arguments: Vec<Type> = types.iter().map(|t| self.resolve(t)).collect();
+                        Type::GenericType(Box::new(Type::DataStruct(name.clone(), span.clone())), arguments, span.clone())
+                    }
+                };
+                let declared: Vec<(String, Option<Type>)> = self.struct_fields(&t).unwrap_or_default();
+                let mut given: Vec<String> = vec![];
+                let mut named: bool = false;
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    let value_type: Option<Type> = self.check_expression(value, locals);
+                    let field: String = match field {
+                        Some(field) => {
+                            named = true;
+                            field.clone()
+                        }
+                        None if named => {
+                            self.error("positional fields must come before named ones".to_string(), value.span());
+                            continue;
+                        }
+                        None => match declared.get(i) {
+                            Some((field, _)) => field.clone(),
+                            None => {
+                                self.error(format!("`{}` has {} fields but more were given", name, declared.len()), value.span());
+                                continue;
+                            }
+                        },
+                    };
+                    self.check_field(&t, &declared, &field, value_type, &mut given, value.span());
+                }
+                for (field, _) in declared.iter().filter(|(field, _)| !given.contains(field)) {
+                    self.error(format!("missing field `{}` in `{}`", field, type_name(&t)), span.clone());
+                }
+                Some(t)
+            }
+            Expression::With(value, fields, span) => {
+                let value_type: Option<Type> = self.check_expression(value, locals);
+                let declared: Option<Vec<(String, Option<Type>)>> = value_type.as_ref().and_then(|t| self.struct_fields(t));
+                if let (Some(value_type), None) = (&value_type, &declared) {
+                    self.error(format!("`with` needs a data struct, found `{}`", type_name(value_type)), span.clone());
+                }
+                let mut given: Vec<String> = vec![];
+                for (field, new_value) in fields.iter() {
+                    let new_type: Option<Type> = self.check_expression(new_value, locals);
+                    if let (Some(t), Some(declared)) = (&value_type, &declared) {
+                        self.check_field(t, declared, field, new_type, &mut given, new_value.span());
+                    }
+                }
+                value_type
+            }
+            Expression::Lambda(parameters, body, _) => {
+                let mut locals: HashMap<String, Type> = locals.clone();
+                for (name, t) in parameters.iter() {
+                    match t {
+                        Some(t) => locals.insert(name.clone(), self.resolve(t)),
+                        None => locals.remove(name),
+                    };
+                }
+                self.check_expression(body, &locals);
+                None
+            }
+            Expression::Block(statements, result, _) => {
+                let mut locals: HashMap<String, Type> = locals.clone();
+                for statement in statements.iter() {
+                    self.check_statement(statement, &mut locals);
+                }
+                self.check_expression(result, &locals)
+            }
+            Expression::Match(value, cases, else_case, span) => {
+                let value_type: Option<Type> = self.check_expression(value, locals);
+                if let Some(value_type) = &value_type {
+                    self.matched_types.insert(span.clone(), value_type.clone());
+                }
+                self.check_coverage(value_type, cases, else_case, span);
+                let mut result_types: Vec<Option<Type>> = vec![];
+                for case in cases.iter().chain(else_case.iter()) {
+                    // The names a case binds have types this checker doesn't track.
+                    let mut locals: HashMap<String, Type> = locals.clone();
+                    for name in case.pattern.bindings() {
+                        locals.remove(&name);
+                    }
+                    if let Some(guard) = &case.guard {
+                        if let Some(guard_type) = self.check_expression(guard, &locals).filter(|t| !matches!(t, Type::Bool(_))) {
+                            self.error(format!("match guard must be `Bool`, found `{}`", type_name(&guard_type)), guard.span());
+                        }
+                    }
+                    let mut result_type: Option<Type> = None;
+                    for statement in case.body.iter() {
+                        result_type = match statement {
+                            Statement::Expression(expression, _) => self.check_expression(expression, &locals),
+                            statement => {
+                                self.check_statement(statement, &mut locals);
+                                None
+                            }
+                        };
+                    }
+                    result_types.push(result_type);
+                }
+                // The cases' results all have to be known and the same for the match's to be.
+                let first: Type = result_types.first().cloned()??;
+                if !result_types.iter().all(|t| t.as_ref().is_some_and(|t| same_type(t, &first))) {
+                    return None;
+                }
+                self.result_types.entry(span.clone()).or_insert(first.clone());
+                Some(first)
+            }
+            // A literal's type is known when its first entry's is.
+            Expression::List(items, _) => {
+                let types: Vec<Option<Type>> = items.iter().map(|item| self.check_expression(item, locals)).collect();
+                Some(Type::Array(Box::new(types.into_iter().next()??), span))
+            }
+            Expression::Map(entries, _) => {
+                let types: Vec<(Option<Type>, Option<Type>)> = entries.iter()
+                    .map(|(key, value)| (self.check_expression(key, locals), self.check_expression(value, locals)))
+                    .collect();
+                let (key, value) = types.into_iter().next()?;
+                let map: Type = Type::Unknown("Map".to_string(), span.clone());
+                Some(Type::GenericType(Box::new(map), vec![key?, value?], span))
+            }
+            Expression::Tuple(items, _) => {
+                let types: Vec<Option<Type>> = items.iter().map(|item| self.check_expression(item, locals)).collect();
+                Some(Type::Tuple(types.into_iter().collect::<Option<Vec<Type>>>()?, span))
+            }
+            Expression::Cpp(..) => None,
+        }
+    }
+    fn check_statement(&mut self, statement: &Statement, locals: &mut HashMap<String, Type>) {
+        match statement {
+            Statement::Let(name, t, value, _) => {
+                let inferred: Option<Type> = self.check_expression(value, locals);
+                if let Some(t) = t {
+                    let t: Type = self.resolve(t);
+                    self.expect_type(value, &t);
+                }
+                match t.as_ref().map(|t| self.resolve(t)).or(inferred) {
+                    Some(t) => locals.insert(name.clone(), t),
+                    None => locals.remove(name),
+                };
+            }
+            Statement::Destructure(pattern, t, value, span) => {
+                let inferred: Option<Type> = self.check_expression(value, locals);
+                let t: Option<Type> = t.as_ref().map(|t| self.resolve(t)).or(inferred);
+                self.check_irrefutable(pattern, t, span);
+                for name in pattern.bindings() {
+                    locals.remove(&name);
+                }
+            }
+            Statement::Expression(expression, _) => {
+                self.check_expression(expression, locals);
+            }
+            _ => {}
+        }
+    }
+
+    // Records `t`, the type `expression` is declared to have, as the type of the matches it ends
+    // in. Their cases may give values of different C++ types, like closures, that only convert to it.
+    fn expect_type(&mut self, expression: &Expression, t: &Type) {
+        match expression {
+            Expression::Match(_, cases, else_case, span) => {
+                self.result_types.insert(span.clone(), t.clone());
+                for case in cases.iter().chain(else_case.iter()) {
+                    if let Some(Statement::Expression(result, _)) = case.body.last() {
+                        self.expect_type(result, t);
+                    }
+                }
+            }
+            Expression::If(_, then_branch, else_branch, _) => {
+                self.expect_type(then_branch, t);
+                self.expect_type(else_branch, t);
+            }
+            Expression::Block(_, result, _) | Expression::Unsafe(result, _) => self.expect_type(result, t),
+            _ => {}
+        }
+    }
+
+    // `t` with every alias replaced by the type it stands for, so types can be compared
+    // structurally. An alias that refers back to itself is left as it is.
+    fn resolve(&self, t: &Type) -> Type {
+        resolve(t, &self.aliases, &mut vec![])
+    }
+    // A procedure's type, like `(Int, String) -> Bool`, with the names in `substitution` replaced.
+    fn signature(&self, args: &[(String, Type)], return_type: &Type, substitution: &HashMap<String, Type>, span: &Span) -> Type {
+        let args: Vec<Type> = args.iter().map(|(_, t)| substitute(&self.resolve(t), substitution)).collect();
+        Type::Function(args, Box::new(substitute(&self.resolve(return_type), substitution)), span.clone())
+    }
+
+    // The fields of the data struct `t` is, like `Pair[Int, String]`, with their types where
+    // they are known. `None` when `t` isn't a data struct.
+    fn struct_fields(&self, t: &Type) -> Option<Vec<(String, Option<Type>)>> {
+        let (name, arguments): (String, Vec<Type>) = match t {
+            Type::GenericType(base, arguments, _) => (type_name(base), arguments.clone()),
+            Type::DataStruct(name, _) | Type::Unknown(name, _) => (name.clone(), vec![]),
+            _ => return None,
+        };
+        let (parameters, fields) = self.structs.get(&name)?;
+        // Without type arguments the fields of a generic struct have unknown types.
+        let known: bool = parameters.len() == arguments.len();
+        let substitution: HashMap<String, Type> = parameters.iter().cloned().zip(arguments).collect();
+        Some(fields.iter().map(|(field, field_type)| {
+            let field_type: Option<Type> = Some(substitute(field_type, &substitution)).filter(|_| known);
+            (field.clone(), field_type)
+        }).collect())
+    }
+    // Checks a field given a value in a construction or `with`, `given` are the ones before it.
+    fn check_field(&mut self, t: &Type, declared: &[(String, Option<Type>)], field: &str, value_type: Option<Type>, given: &mut Vec<String>, span: Span) {
+        if given.iter().any(|name| name == field) {
+            self.error(format!("field `{}` is given more than once", field), span);
+            return;
+        }
+        given.push(field.to_string());
+        let Some((_, field_type)) = declared.iter().find(|(name, _)| name == field) else {
+            self.error(format!("`{}` has no field `{}`", type_name(t), field), span);
+            return;
+        };
+        if let (Some(field_type), Some(value_type)) = (field_type, value_type) {
+            if !same_type(field_type, &value_type) {
+                self.error(format!(
+                    "field `{}` of `{}` must be `{}`, found `{}`",
+                    field, type_name(t), type_name(field_type), type_name(&value_type)
+                ), span);
+            }
+        }
+    }
+
+    // A `let` has nowhere to go when its pattern doesn't match, so it has to match every value.
+    fn check_irrefutable(&mut self, pattern: &Pattern, t: Option<Type>, span: &Span) {
+        let structs: HashMap<String, Vec<(String, Type)>> = self.struct_declarations();
+        let mut coverage: Coverage = Coverage::new(&self.enums, &structs);
+        let row: Vec<Space> = vec![coverage.lower(pattern, t.as_ref())];
+        let missing: Option<Vec<Space>> = coverage.useful(&[row], &[Space::Wildcard], &[t]);
+        let errors: Vec<TypeError> = coverage.errors;
+        if let (Some(witness), true) = (missing, errors.is_empty()) {
+            self.error(format!("refutable pattern in `let`, `{}` is not covered", display(&witness[0])), span.clone());
+        }
+        self.errors.extend(errors);
+    }
+
+    // Reports a match that misses values, with an example of one, and cases that can't be reached.
+    fn check_coverage(&mut self, t: Option<Type>, cases: &[MatchCase], else_case: &Option<MatchCase>, span: &Span) {
+        let structs: HashMap<String, Vec<(String, Type)>> = self.struct_declarations();
+        let enums: HashMap<String, Vec<EnumVarient>> = self.enums.clone();
+        let mut coverage: Coverage = Coverage::new(&enums, &structs);
+        let types: Vec<Option<Type>> = vec![t.clone()];
+        let mut rows: Vec<Vec<Space>> = vec![];
+        for case in cases.iter() {
+            let errors: usize = coverage.errors.len();
+            let row: Vec<Space> = vec![coverage.lower(&case.pattern, t.as_ref())];
+            // A pattern that doesn't fit the type was already reported, it covers nothing.
+            if coverage.errors.len() > errors {
+                continue;
+            }
+            if coverage.useful(&rows, &row, &types).is_none() {
+                self.warning("unreachable match case, the cases before it match everything it does".to_string(), case.span.clone());
+            }
+            // A guard can fail, so a guarded case doesn't cover anything for the cases after it.
+            if case.guard.is_none() {
+                rows.push(row);
+            }
+        }
+        let missing: Option<Vec<Space>> = coverage.useful(&rows, &[Space::Wildcard], &types);
+        self.errors.extend(coverage.errors);
+        match (missing, else_case) {
+            (Some(witness), None) => {
+                self.error(format!("non-exhaustive match, `{}` is not covered", display(&witness[0])), span.clone());
+            }
+            (None, Some(else_case)) => {
+                self.warning("`else` case can never be reached, the cases before it match every value".to_string(), else_case.span.clone());
+            }
+            _ => {}
         }
     }
-}
\ No newline at end of file
+    fn struct_declarations(&self) -> HashMap<String, Vec<(String, Type)>> {
+        self.structs.iter().map(|(name, (_, fields))| (name.clone(), fields.clone())).collect()
+    }
+
+    fn error(&mut self, message: String, span: Span) {
+        self.errors.push(TypeError { message, span });
+    }
+    fn warning(&mut self, message: String, span: Span) {
+        self.warnings.push(TypeError { message, span });
+    }
+}
+
+fn parameter_names(generics: &[(Type, parser::parser::GenericType, Vec<Type>)]) -> Vec<String> {
+    generics.iter().map(|(t, _, _)| type_name(t)).collect()
+}
+
+fn resolve(t: &Type, aliases: &HashMap<String, (Vec<String>, Type)>, expanding: &mut Vec<String>) -> Type {
+    match t {
+        // Uses of an alias before its declaration were parsed as unknown types.
+        Type::Alias(name, _) | Type::Unknown(name, _) if !expanding.contains(name) => {
+            match aliases.get(name) {
+                Some((parameters, aliased)) if parameters.is_empty() => {
+                    expanding.push(name.clone());
+                    let resolved: Type = resolve(aliased, aliases, expanding);
+                    expanding.pop();
+                    resolved
+                }
+                _ => t.clone(),
+            }
+        }
+        Type::GenericType(base, arguments, span) => {
+            let arguments: Vec<Type> = arguments.iter().map(|t| resolve(t, aliases, expanding)).collect();
+            if let Type::Alias(name, _) | Type::Unknown(name, _) = base.as_ref() {
+                let alias: Option<&(Vec<String>, Type)> = aliases.get(name).filter(|(parameters, _)| parameters.len() == arguments.len());
+                if let (Some((parameters, aliased)), false) = (alias, expanding.contains(name)) {
+                    let substitution: HashMap<String, Type> = parameters.iter().cloned().zip(arguments).collect();
+                    expanding.push(name.clone());
+                    let resolved: Type = resolve(&substitute(aliased, &substitution), aliases, expanding);
+                    expanding.pop();
+                    return resolved;
+                }
+            }
+            Type::GenericType(base.clone(), arguments, span.clone())
+        }
+        Type::Optional(inner, span) => Type::Optional(Box::new(resolve(inner, aliases, expanding)), span.clone()),
+        Type::Array(inner, span) => Type::Array(Box::new(resolve(inner, aliases, expanding)), span.clone()),
+        Type::Function(args, return_type, span) => {
+            let args: Vec<Type> = args.iter().map(|t| resolve(t, aliases, expanding)).collect();
+            Type::Function(args, Box::new(resolve(return_type, aliases, expanding)), span.clone())
+        }
+        Type::Tuple(types, span) => Type::Tuple(types.iter().map(|t| resolve(t, aliases, expanding)).collect(), span.clone()),
+        t => t.clone(),
+    }
+}
+
+// `t` with the generic parameters named in `substitution` replaced.
+fn substitute(t: &Type, substitution: &HashMap<String, Type>) -> Type {
+    match t {
+        Type::GenericParameter(name, _) | Type::Generic(name, _) |
+        Type::Alias(name, _) | Type::Object(name, _) | Type::Unknown(name, _) if substitution.contains_key(name) => {
+            substitution[name].clone()
+        }
+        Type::GenericType(base, arguments, span) => {
+            let arguments: Vec<Type> = arguments.iter().map(|t| substitute(t, substitution)).collect();
+            Type::GenericType(Box::new(substitute(base, substitution)), arguments, span.clone())
+        }
+        Type::Optional(inner, span) => Type::Optional(Box::new(substitute(inner, substitution)), span.clone()),
+        Type::Array(inner, span) => Type::Array(Box::new(substitute(inner, substitution)), span.clone()),
+        Type::Function(args, return_type, span) => {
+            let args: Vec<Type> = args.iter().map(|t| substitute(t, substitution)).collect();
+            Type::Function(args, Box::new(substitute(return_type, substitution)), span.clone())
+        }
+        Type::Tuple(types, span) => Type::Tuple(types.iter().map(|t| substitute(t, substitution)).collect(), span.clone()),
+        t => t.clone(),
+    }
+}
+
+// Whether two resolved types are the same, ignoring where they were written. Named types are
+// the same when their names are, however the parser classified them.
+pub(crate) fn same_type(a: &Type, b: &Type) -> bool {
+    let same_types = |a: &[Type], b: &[Type]| a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same_type(a, b));
+    match (a, b) {
+        (Type::Unit(_), Type::Unit(_)) | (Type::Int(_), Type::Int(_)) | (Type::Float(_), Type::Float(_)) |
+        (Type::Char(_), Type::Char(_)) | (Type::Bool(_), Type::Bool(_)) => true,
+        (Type::GenericType(a, x, _), Type::GenericType(b, y, _)) => same_type(a, b) && same_types(x, y),
+        (Type::Optional(a, _), Type::Optional(b, _)) | (Type::Array(a, _), Type::Array(b, _)) => same_type(a, b),
+        (Type::Function(x, a, _), Type::Function(y, b, _)) => same_types(x, y) && same_type(a, b),
+        (Type::Tuple(x, _), Type::Tuple(y, _)) => same_types(x, y),
+        _ => matches!((type_base_name(a), type_base_name(b)), (Some(a), Some(b)) if a == b),
+    }
+}
+
+// The name of a named type, like `Shape` or the `Pair` of `Pair[Int, String]`.
+pub(crate) fn type_base_name(t: &Type) -> Option<&str> {
+    match t {
+        Type::GenericParameter(name, _) | Type::Generic(name, _) |
+        Type::DataEnum(name, _) | Type::DataStruct(name, _) |
+        Type::Alias(name, _) | Type::Object(name, _) | Type::Unknown(name, _) => Some(name),
+        _ => None,
+    }
+}
+
+// A type written out the way it is in source, for messages.
+pub(crate) fn type_name(t: &Type) -> String {
+    match t {
+        Type::Unit(_) => "Unit".to_string(),
+        Type::Int(_) => "Int".to_string(),
+        Type::Float(_) => "Float".to_string(),
+        Type::Char(_) => "Char".to_string(),
+        Type::Bool(_) => "Bool".to_string(),
+        Type::GenericParameter(name, _) | Type::Generic(name, _) |
+        Type::DataEnum(name, _) | Type::DataStruct(name, _) |
+        Type::Alias(name, _) | Type::Object(name, _) | Type::Unknown(name, _) => name.clone(),
+        Type::GenericType(base, arguments, _) => {
+            let arguments: Vec<String> = arguments.iter().map(type_name).collect();
+            format!("{}[{}]", type_name(base), arguments.join(", "))
+        }
+        Type::Optional(inner, _) => format!("{}?", type_name(inner)),
+        Type::Array(inner, _) => format!("[{}]", type_name(inner)),
+        Type::Function(args, return_type, _) => {
+            let args: Vec<String> = args.iter().map(type_name).collect();
+            format!("({}) -> {}", args.join(", "), type_name(return_type))
+        }
+        Type::Tuple(types, _) => {
+            let types: Vec<String> = types.iter().map(type_name).collect();
+            format!("({})", types.join(", "))
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use lexer::{layout::Layout, lexer::Lexer, span::FileId};
+    use parser::parser::Parser;
+    use super::*;
+
+    // The error messages for `source`.
+    fn check(source: &str) -> Vec<String> {
+        let mut parser: Parser = Parser::new(Layout::new(Lexer::new(source, FileId(0))).collect());
+        let (_, errors) = parser.parse();
+        assert!(errors.is_empty(), "{:?}", errors);
+        TypeChecker::new(parser).check().into_iter().map(|error| error.message).collect()
+    }
+
+    #[test]
+    fn lists_are_indexed_and_sliced_by_int() {
+        assert_eq!(check("module M
+procedure F(xs: [Int]) -> Int = xs[true]
+procedure G(xs: [Int]) -> [Int] = xs[1..false]
+procedure H(xs: [Int], i: Int) -> [Int] = xs[i..][..i]
+"), vec!["index must be `Int`, found `Bool`", "slice bound must be `Int`, found `Bool`"]);
+    }
+    #[test]
+    fn map_lookups_take_the_key_type_and_may_miss() {
+        assert_eq!(check("module M
+data P(a: Int)
+procedure F(m: Map[String, Int]) -> Int = m[1]
+procedure G(m: Map[String, Int]) -> P = P(a = m[\"k\"])
+procedure H(m: Map[String, Int]) -> P = unsafe P(a = m[\"k\"])
+procedure K(xs: [Int]) -> P = P(a = xs[0])
+"), vec!["map key must be `String`, found `Int`", "field `a` of `P` must be `Int`, found `Int?`"]);
+    }
+    #[test]
+    fn constructions_give_every_field_once() {
+        assert_eq!(check("module M
+procedure A() -> Point = Point(1, y = 2)
+procedure B() -> Point = Point(x = 1)
+procedure C() -> Point = Point(x = 1, y = 2, z = 3)
+procedure D() -> Point = Point(x = 1, x = 2, y = 3)
+procedure E() -> Point = Point(x = 1, 2)
+procedure F() -> Point = Point(1, 2, 3)
+procedure G() -> Point = Point(x = true, y = 2)
+data Point(x: Int, y: Int)
+"), vec![
+            "missing field `y` in `Point`",
+            "`Point` has no field `z`",
+            "field `x` is given more than once",
+            "positional fields must come before named ones",
+            "missing field `y` in `Point`",
+            "`Point` has 2 fields but more were given",
+            "field `x` of `Point` must be `Int`, found `Bool`",
+        ]);
+    }
+    #[test]
+    fn updates_and_field_access_need_a_declared_field() {
+        assert_eq!(check("module M
+data Point(x: Int, y: Int)
+procedure A(p: Point) -> Point = p with { y = 3, w = 1 }
+procedure B(n: Int) -> Int = n with { y = 3 }
+procedure C(p: Point) -> Int = p.z
+procedure D(p: Point) -> Int = p.x
+"), vec!["`Point` has no field `w`", "`with` needs a data struct, found `Int`", "`Point` has no field `z`"]);
+    }
+}